@@ -0,0 +1,312 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    history::Scan,
+    metadata::write_metadata,
+    types::{Mod, ModKey},
+    util::{get_config_dir, is_disable_marker, list_files},
+};
+
+/// Number of archived versions kept per mod.
+const KEPT_VERSIONS: usize = 5;
+
+/// A compressed copy of a mod folder, taken when a new version of it was seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedVersion {
+    pub mod_id: ModKey,
+    pub name: String,
+    pub version: String,
+    pub fingerprint: u64,
+    /// Unix timestamp of the snapshot.
+    pub time: u64,
+    /// Name of the archive file in the archive folder.
+    pub file: String,
+}
+
+/// Past versions of mod folders, kept to roll back broken updates.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ModArchive {
+    pub versions: Vec<ArchivedVersion>,
+}
+
+impl ModArchive {
+    fn get_archive_dir() -> Option<PathBuf> {
+        Some(get_config_dir()?.join("archive"))
+    }
+
+    pub fn load() -> Result<Self> {
+        if let Some(path) = Self::get_archive_dir() {
+            let index_path = path.join("archive.json");
+            let index_contents = fs::read_to_string(index_path)?;
+            let archive = serde_json::from_str(&index_contents)?;
+            Ok(archive)
+        } else {
+            Err(anyhow!(
+                "Cannot load mod archive: directory somehow missing"
+            ))
+        }
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = Self::get_archive_dir() {
+            if !path.exists() {
+                fs::create_dir_all(&path)?;
+            }
+            let index_path = path.join("archive.json");
+            let index_contents = serde_json::to_string_pretty(self)?;
+            fs::write(index_path, index_contents)?;
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Cannot save mod archive: directory somehow missing"
+            ))
+        }
+    }
+
+    pub fn versions_of<'a>(
+        &'a self,
+        key: &'a ModKey,
+    ) -> impl Iterator<Item = (usize, &'a ArchivedVersion)> {
+        self.versions
+            .iter()
+            .enumerate()
+            .filter(move |(_, v)| v.mod_id == *key)
+    }
+
+    /// Versions of the scanned mods that are not archived yet, with the folders to archive them
+    /// from.
+    pub fn pending(&self, mod_list: &[Mod], scan: &Scan) -> Vec<(PathBuf, ArchivedVersion)> {
+        mod_list
+            .iter()
+            .zip(&scan.mods)
+            .map(|(m, snapshot)| {
+                // Local mods are keyed by folder name, which can hold characters unfit for a file name
+                let prefix: String = snapshot
+                    .id
+                    .to_string()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect();
+                let version = ArchivedVersion {
+                    mod_id: snapshot.id.clone(),
+                    name: snapshot.name.clone(),
+                    version: snapshot.version.clone(),
+                    fingerprint: snapshot.fingerprint,
+                    time: scan.time,
+                    file: format!("{prefix}_{}_{:016x}.zip", scan.time, snapshot.fingerprint),
+                };
+                (m.path.clone(), version)
+            })
+            .filter(|(_, version)| !self.contains(version))
+            .collect()
+    }
+
+    /// Whether the same files of a mod are archived already.
+    pub fn contains(&self, version: &ArchivedVersion) -> bool {
+        self.versions
+            .iter()
+            .any(|v| v.mod_id == version.mod_id && v.fingerprint == version.fingerprint)
+    }
+
+    /// Compresses each pending mod folder on its own, returning the versions archived and the
+    /// errors of the others. Leaves the index alone, so that it can run in the background.
+    pub fn archive(
+        pending: Vec<(PathBuf, ArchivedVersion)>,
+    ) -> (Vec<ArchivedVersion>, Vec<String>) {
+        match Self::get_archive_dir() {
+            Some(dir) => compress_versions(&dir, pending),
+            None => (
+                Vec::new(),
+                vec!["Cannot archive mods: directory somehow missing".to_string()],
+            ),
+        }
+    }
+
+    /// Adds archived versions to the index, dropping the oldest ones above the kept amount.
+    pub fn add(&mut self, versions: Vec<ArchivedVersion>) {
+        let Some(dir) = Self::get_archive_dir() else {
+            return;
+        };
+        for version in versions {
+            if self.contains(&version) {
+                let _ = fs::remove_file(dir.join(&version.file));
+                continue;
+            }
+            let key = version.mod_id.clone();
+            self.versions.push(version);
+            self.prune(&dir, &key);
+        }
+    }
+
+    /// Drops the oldest versions of a mod above the kept amount.
+    fn prune(&mut self, dir: &Path, key: &ModKey) {
+        let count = self.versions.iter().filter(|v| v.mod_id == *key).count();
+        for _ in KEPT_VERSIONS..count {
+            if let Some(i) = self.versions.iter().position(|v| v.mod_id == *key) {
+                let version = self.versions.remove(i);
+                let _ = fs::remove_file(dir.join(version.file));
+            }
+        }
+    }
+
+    /// Extracts an archived version next to the original mod folder, disabled, and returns its
+    /// path. The copy loses the workshop id, so it is told apart from the original.
+    pub fn restore(&self, index: usize, mods_path: &Path, original: &Mod) -> Result<PathBuf> {
+        let version = self
+            .versions
+            .get(index)
+            .ok_or_else(|| anyhow!("Cannot restore mod: archived version missing"))?;
+        let dir = Self::get_archive_dir()
+            .ok_or_else(|| anyhow!("Cannot restore mod: directory somehow missing"))?;
+        let folder = original
+            .path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| version.mod_id.to_string());
+        // Without the workshop id suffix, the game treats the copy as a local mod
+        let folder = folder
+            .strip_suffix(&format!("_{}", version.mod_id))
+            .unwrap_or(&folder);
+        let target = mods_path.join(format!("{folder} (rollback {})", version.version));
+        if target.exists() {
+            return Err(anyhow!(
+                "Cannot restore mod: {} already exists",
+                target.display()
+            ));
+        }
+        extract_zip(&dir.join(&version.file), &target)?;
+        let mut metadata = Mod::from_path(target.clone())?.metadata;
+        metadata.id = 0;
+        metadata.name = format!("{} (rollback {})", metadata.name, version.version);
+        write_metadata(&target, &metadata)?;
+        File::create(target.join("disable.it"))?;
+        Ok(target)
+    }
+}
+
+/// Compresses each mod folder into the archive folder, keeping going past failures.
+fn compress_versions(
+    dir: &Path,
+    pending: Vec<(PathBuf, ArchivedVersion)>,
+) -> (Vec<ArchivedVersion>, Vec<String>) {
+    if let Err(e) = fs::create_dir_all(dir) {
+        return (Vec::new(), vec![format!("Cannot archive mods: {e}")]);
+    }
+    let mut archived = Vec::new();
+    let mut errors = Vec::new();
+    for (path, version) in pending {
+        let target = dir.join(&version.file);
+        match compress_dir(&path, &target, "", &|_| false) {
+            Ok(()) => archived.push(version),
+            Err(e) => {
+                let _ = fs::remove_file(target);
+                errors.push(format!("Cannot archive {}: {e}", version.name));
+            }
+        }
+    }
+    (archived, errors)
+}
+
+/// Compresses a mod folder under the given prefix, leaving out the marker that disables it and
+/// the files the filter excludes.
+pub fn compress_dir(
+    dir: &Path,
+    target: &Path,
+    prefix: &str,
+    exclude: &dyn Fn(&str) -> bool,
+) -> Result<()> {
+    // A folder moved away meanwhile would otherwise give an empty archive
+    if !dir.is_dir() {
+        return Err(anyhow!("{} is not a folder", dir.display()));
+    }
+    let mut zip = ZipWriter::new(File::create(target)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in list_files(dir) {
+        let Ok(relative) = file.strip_prefix(dir) else {
+            continue;
+        };
+        let name = relative.to_string_lossy().replace('\\', "/");
+        if is_disable_marker(&name) || exclude(&name) {
+            continue;
+        }
+        zip.start_file(format!("{prefix}{name}"), options)?;
+        io::copy(&mut File::open(&file)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Extracts a zip file into a folder, refusing entries that would land outside of it.
+pub fn extract_zip(archive: &Path, target: &Path) -> Result<()> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let Some(relative) = entry.enclosed_name().map(Path::to_path_buf) else {
+            return Err(anyhow!("Unsafe path in archive: {}", entry.name()));
+        };
+        let path = target.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{types::ModKey, util::test::TempDir};
+
+    use super::{compress_dir, compress_versions, extract_zip, ArchivedVersion};
+
+    #[test]
+    fn compress_and_extract() {
+        let dir = TempDir::new("archive");
+        let source = dir.join("source");
+        fs::create_dir_all(source.join("content")).unwrap();
+        fs::write(source.join("metadata.xml"), "<metadata></metadata>").unwrap();
+        fs::write(source.join("content/items.xml"), "<items />").unwrap();
+        fs::write(source.join("disable.it"), "").unwrap();
+
+        compress_dir(&source, &dir.join("mod.zip"), "", &|_| false).unwrap();
+        extract_zip(&dir.join("mod.zip"), &dir.join("restored")).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("restored/content/items.xml")).unwrap(), "<items />", "Nested files should be restored");
+        assert!(!dir.join("restored/disable.it").exists(), "The disable marker should not be archived");
+    }
+
+    #[test]
+    fn archive_each_mod() {
+        let dir = TempDir::new("archive-versions");
+        fs::create_dir_all(dir.join("mods/good")).unwrap();
+        fs::write(dir.join("mods/good/main.lua"), "").unwrap();
+        fs::write(dir.join("mods/good/disable.it"), "").unwrap();
+        let version = |name: &str| ArchivedVersion { mod_id: ModKey::Local(name.to_string()), name: name.to_string(), version: "1".to_string(), fingerprint: 0, time: 0, file: format!("{name}.zip") };
+
+        let pending = vec![(dir.join("mods/missing"), version("missing")), (dir.join("mods/good"), version("good"))];
+        let (archived, errors) = compress_versions(&dir.join("archive"), pending);
+        assert_eq!(archived, [version("good")], "A failing mod should not stop the others from being archived");
+        assert_eq!(errors.len(), 1);
+        assert!(!dir.join("archive/missing.zip").exists(), "Failed archives should not be left behind");
+        extract_zip(&dir.join("archive/good.zip"), &dir.join("restored")).unwrap();
+        assert!(dir.join("restored/main.lua").exists());
+        assert!(!dir.join("restored/disable.it").exists(), "Archived versions should not be disabled");
+    }
+}
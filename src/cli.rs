@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    doctor::{apply_safe_fixes, diagnose},
+    log::LogReport,
+    types::AppConfig,
+    types::Mod,
+    validate::{validate_mod, Severity},
+};
+
+const USAGE: &str = "Usage: icy-isaac-mod-manager [OPTIONS] [COMMAND]
+
+Runs the graphical mod manager when no command is given.
+
+Commands:
+  errors [--log <PATH>]  List Lua errors in the game log, grouped by mod
+  doctor [--fix]         Find broken mod folders, --fix applies the safe fixes
+  validate <MOD>         Check a mod folder, name or id before uploading it
+  help                   Print this message
+
+Options:
+  --instance <NAME>      Game instance to use instead of the selected one";
+
+/// Names the commands can be run by.
+const COMMANDS: [&str; 6] = ["errors", "doctor", "validate", "help", "--help", "-h"];
+
+/// Whether the arguments ask for a command, rather than for the graphical mod manager. Other
+/// arguments, like the ones some desktops pass to apps, are left alone.
+pub fn is_command(args: &[String]) -> bool {
+    split_instance(args)
+        .1
+        .first()
+        .is_some_and(|a| COMMANDS.contains(&a.as_str()))
+}
+
+/// Runs a command line command, returning the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    match dispatch(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+/// Takes `--instance <NAME>` out of the arguments, so that it can go before the command.
+fn split_instance(args: &[String]) -> (Option<&str>, Vec<String>) {
+    match args.iter().position(|a| a == "--instance") {
+        Some(i) => (
+            args.get(i + 1).map(String::as_str),
+            [&args[..i], args.get(i + 2..).unwrap_or_default()].concat(),
+        ),
+        None => (None, args.to_vec()),
+    }
+}
+
+fn dispatch(args: &[String]) -> Result<()> {
+    // Only checks that the instance name is there
+    get_option(args, "--instance")?;
+    let (instance, args) = split_instance(args);
+    match args.first().map(String::as_str) {
+        Some("errors") => errors(&args[1..], load_config(instance)?),
+        Some("validate") => validate(&args[1..], load_config(instance)?),
+        Some("doctor") => doctor(&args[1..], load_config(instance)?),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => Err(anyhow!("Unknown command: {command}\n\n{USAGE}")),
+        None => Ok(()),
+    }
+}
+
+/// Returns the value following a flag like `--log <PATH>`.
+fn get_option<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>> {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => args
+            .get(i + 1)
+            .map(|v| Some(v.as_str()))
+            .ok_or_else(|| anyhow!("Missing value for {flag}")),
+        None => Ok(None),
+    }
+}
+
+/// Loads the config, switching to the given instance if any.
+fn load_config(instance: Option<&str>) -> Result<AppConfig> {
+    let mut config = AppConfig::load_or_default();
+    if let Some(instance) = instance {
+        config.select_instance(instance)?;
+    }
+    Ok(config)
+}
+
+fn errors(args: &[String], config: AppConfig) -> Result<()> {
+    let log_path = match get_option(args, "--log")? {
+        Some(path) => PathBuf::from(path),
+        None => config
+            .get_log_path()
+            .ok_or_else(|| anyhow!("Cannot find the game log, pass it with --log"))?,
+    };
+    let mod_list = Mod::load_all(config.get_mods_path(), config.disable_strategy)?;
+    let report = LogReport::from_file(&log_path, &mod_list)?;
+
+    if report.mods.is_empty() {
+        println!("No errors attributed to mods in {}", log_path.display());
+    }
+    for errors in &report.mods {
+        let name = mod_list
+            .iter()
+            .find(|m| m.key() == errors.mod_id)
+            .map_or("<unknown mod>", |m| m.metadata.name.as_str());
+        println!("{name} ({}): {} errors", errors.mod_id, errors.count);
+        for message in &errors.latest {
+            println!("    {message}");
+        }
+    }
+    if report.unattributed > 0 {
+        println!(
+            "{} errors could not be attributed to a mod",
+            report.unattributed
+        );
+    }
+    Ok(())
+}
+
+fn validate(args: &[String], config: AppConfig) -> Result<()> {
+    let target = args
+        .first()
+        .filter(|a| !a.starts_with("--"))
+        .ok_or_else(|| anyhow!("Missing mod to validate\n\n{USAGE}"))?;
+    let path = if Path::new(target).join("metadata.xml").exists() {
+        PathBuf::from(target)
+    } else {
+        let mod_list = Mod::load_all(config.get_mods_path(), config.disable_strategy)?;
+        mod_list
+            .into_iter()
+            .find(|m| {
+                // Mods never uploaded have no id to look them up by
+                (m.metadata.id != 0 && m.metadata.id.to_string() == *target)
+                    || m.metadata.name.eq_ignore_ascii_case(target)
+                    || m.metadata.directory.eq_ignore_ascii_case(target)
+            })
+            .map(|m| m.path)
+            .ok_or_else(|| anyhow!("No mod found for {target}"))?
+    };
+
+    let report = validate_mod(&path, &config.export_ignore);
+    for issue in &report.issues {
+        println!("{}: {}", issue.severity, issue.message);
+    }
+    if report.passed() {
+        println!("PASS: {} is ready to upload", path.display());
+        Ok(())
+    } else {
+        Err(anyhow!("FAIL: {} has errors to fix", path.display()))
+    }
+}
+
+fn doctor(args: &[String], config: AppConfig) -> Result<()> {
+    let mut diagnoses = diagnose(config.get_mods_path())?;
+    if args.iter().any(|a| a == "--fix") {
+        let applied = apply_safe_fixes(&diagnoses)?;
+        println!("Applied {applied} fixes");
+        diagnoses = diagnose(config.get_mods_path())?;
+    }
+
+    if diagnoses.is_empty() {
+        println!("No problems found in {}", config.get_mods_path().display());
+    }
+    for d in &diagnoses {
+        let safe = if d.fix.is_some() { " (safe fix)" } else { "" };
+        println!("{} {}: {}", d.severity, d.folder, d.message);
+        println!("    Fix: {}{safe}", d.suggestion);
+    }
+    if diagnoses.iter().any(|d| d.severity == Severity::Error) {
+        return Err(anyhow!("{} problems left to fix", diagnoses.len()));
+    }
+    Ok(())
+}
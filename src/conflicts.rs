@@ -0,0 +1,82 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::types::{Mod, ModKey};
+
+/// Content files the game merges between mods instead of letting one override the others.
+const MERGED_CONTENT_FILES: [&str; 10] = [
+    "content/items.xml",
+    "content/itempools.xml",
+    "content/players.xml",
+    "content/entities2.xml",
+    "content/music.xml",
+    "content/sounds.xml",
+    "content/pocketitems.xml",
+    "content/costumes2.xml",
+    "content/challenges.xml",
+    "content/cutscenes.xml",
+];
+
+/// Files shipped by two enabled mods for the same path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideConflict {
+    pub mods: (ModKey, ModKey),
+    pub paths: Vec<String>,
+}
+
+pub fn find_override_conflicts(mod_list: &[Mod]) -> Vec<OverrideConflict> {
+    let mut owners: HashMap<&str, Vec<ModKey>> = HashMap::new();
+    for m in mod_list.iter().filter(|m| m.enabled()) {
+        for file in &m.resource_files {
+            if !MERGED_CONTENT_FILES.contains(&file.as_str()) {
+                owners.entry(file).or_default().push(m.key());
+            }
+        }
+    }
+
+    let mut conflicts: BTreeMap<(ModKey, ModKey), Vec<String>> = BTreeMap::new();
+    for (file, ids) in owners.into_iter().filter(|(_, ids)| ids.len() > 1) {
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                let pair = (a.min(b).clone(), a.max(b).clone());
+                conflicts.entry(pair).or_default().push(file.to_string());
+            }
+        }
+    }
+    conflicts
+        .into_iter()
+        .map(|(mods, mut paths)| {
+            paths.sort();
+            OverrideConflict { mods, paths }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{types::{DisableStrategy, Mod}, util::test::{write_mod, TempDir}};
+
+    use super::find_override_conflicts;
+
+    #[test]
+    fn override_conflicts() {
+        let dir = TempDir::new("conflicts");
+        let mods_path = dir.join("mods");
+        for (folder, files) in [("a", &["resources/gfx/isaac.png", "resources/sfx/tear.wav", "content/items.xml"][..]), ("b", &["resources/gfx/isaac.png", "resources/sfx/tear.wav", "content/items.xml"]), ("c", &["resources/gfx/isaac.png"]), ("d", &["resources/gfx/isaac.png", "disable.it"])] {
+            let path = write_mod(&mods_path, folder, "");
+            for file in files {
+                let path = path.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, "").unwrap();
+            }
+        }
+        let mod_list = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        let conflicts: Vec<(String, String, Vec<String>)> = find_override_conflicts(&mod_list).into_iter().map(|c| (c.mods.0.to_string(), c.mods.1.to_string(), c.paths)).collect();
+        assert_eq!(conflicts, vec![
+            ("a".to_string(), "b".to_string(), vec!["resources/gfx/isaac.png".to_string(), "resources/sfx/tear.wav".to_string()]),
+            ("a".to_string(), "c".to_string(), vec!["resources/gfx/isaac.png".to_string()]),
+            ("b".to_string(), "c".to_string(), vec!["resources/gfx/isaac.png".to_string()]),
+        ], "Merged content files and disabled mods should not conflict");
+    }
+}
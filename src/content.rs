@@ -0,0 +1,376 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::types::{Mod, ModKey};
+
+/// Content files adding entries to the game, merged between mods.
+pub const CONTENT_FILES: [&str; 6] = [
+    "items.xml",
+    "players.xml",
+    "entities2.xml",
+    "music.xml",
+    "sounds.xml",
+    "pocketitems.xml",
+];
+
+/// An entry added by a content file, such as an item or an entity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentEntry {
+    pub file: &'static str,
+    pub tag: String,
+    pub name: Option<String>,
+    pub id: Option<String>,
+}
+
+impl ContentEntry {
+    /// Groups entries sharing names and ids, e.g. passive and active items.
+    fn group(&self) -> &str {
+        match self.tag.as_str() {
+            "passive" | "active" | "familiar" => "item",
+            tag => tag,
+        }
+    }
+}
+
+/// What a mod actually adds, derived from its files rather than its self-declared tags.
+#[derive(Debug, Clone, Default)]
+pub struct ContentSummary {
+    pub entries: Vec<ContentEntry>,
+    pub has_lua: bool,
+    pub has_shaders: bool,
+    pub has_music: bool,
+    pub has_rooms: bool,
+    /// Facets the mod matches, worked out once so that filtering the mod list stays cheap.
+    facets: Vec<ContentFacet>,
+}
+
+impl ContentSummary {
+    /// Builds the summary of a mod folder from its files, relative to the folder, lowercase and
+    /// with `/` separators.
+    pub fn from_files(path: &Path, files: &[String]) -> Self {
+        let mut entries = Vec::new();
+        for file in CONTENT_FILES {
+            let path = find_file(&path.join("content"), file);
+            if let Some(contents) = path.and_then(|p| fs::read_to_string(p).ok()) {
+                entries.extend(parse_content(file, &contents));
+            }
+        }
+        let has_file = |f: &dyn Fn(&str) -> bool| files.iter().any(|file| f(file));
+        let mut summary = Self {
+            has_lua: has_file(&|f| f.ends_with(".lua")),
+            has_shaders: has_file(&|f| f == "content/shaders.xml" || f.contains("/shaders/")),
+            has_music: has_file(&|f| f == "content/music.xml" || f.ends_with(".ogg")),
+            has_rooms: has_file(&|f| f.contains("rooms/") && f.ends_with(".stb")),
+            entries,
+            facets: Vec::new(),
+        };
+        summary.facets = ContentFacet::ALL
+            .into_iter()
+            .filter(|facet| summary.matches_facet(*facet))
+            .collect();
+        summary
+    }
+
+    fn count(&self, file: &str, tags: &[&str]) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.file == file && tags.contains(&e.tag.as_str()))
+            .count()
+    }
+
+    pub fn items(&self) -> usize {
+        self.count("items.xml", &["passive", "active", "familiar"])
+    }
+
+    pub fn trinkets(&self) -> usize {
+        self.count("items.xml", &["trinket"])
+    }
+
+    pub fn cards(&self) -> usize {
+        self.count("pocketitems.xml", &["card", "rune"])
+    }
+
+    pub fn characters(&self) -> usize {
+        self.count("players.xml", &["player"])
+    }
+
+    pub fn has_facet(&self, facet: ContentFacet) -> bool {
+        facet == ContentFacet::All || self.facets.contains(&facet)
+    }
+
+    fn matches_facet(&self, facet: ContentFacet) -> bool {
+        match facet {
+            ContentFacet::All => true,
+            ContentFacet::Items => self.items() > 0,
+            ContentFacet::Trinkets => self.trinkets() > 0,
+            ContentFacet::Cards => self.cards() > 0,
+            ContentFacet::Characters => self.characters() > 0,
+            ContentFacet::Lua => self.has_lua,
+            ContentFacet::Shaders => self.has_shaders,
+            ContentFacet::Music => self.has_music,
+            ContentFacet::Rooms => self.has_rooms,
+        }
+    }
+
+    /// Short human readable description of the summary.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = [
+            (self.items(), "item", "items"),
+            (self.trinkets(), "trinket", "trinkets"),
+            (self.cards(), "card or rune", "cards and runes"),
+            (self.characters(), "character", "characters"),
+        ]
+        .iter()
+        .filter(|(count, ..)| *count > 0)
+        .map(|(count, singular, plural)| match count {
+            1 => format!("1 {singular}"),
+            _ => format!("{count} {plural}"),
+        })
+        .collect();
+        for (present, name) in [
+            (self.has_lua, "Lua code"),
+            (self.has_shaders, "shaders"),
+            (self.has_music, "music"),
+            (self.has_rooms, "room files"),
+        ] {
+            if present {
+                lines.push(name.to_string());
+            }
+        }
+        lines
+    }
+
+    /// Number of entries per content file, skipping empty ones.
+    pub fn counts(&self) -> Vec<(&'static str, usize)> {
+        CONTENT_FILES
+            .iter()
+            .map(|file| {
+                (
+                    *file,
+                    self.entries.iter().filter(|e| e.file == *file).count(),
+                )
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+/// Content a mod list can be filtered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentFacet {
+    #[default]
+    All,
+    Items,
+    Trinkets,
+    Cards,
+    Characters,
+    Lua,
+    Shaders,
+    Music,
+    Rooms,
+}
+
+impl ContentFacet {
+    pub const ALL: [Self; 9] = [
+        Self::All,
+        Self::Items,
+        Self::Trinkets,
+        Self::Cards,
+        Self::Characters,
+        Self::Lua,
+        Self::Shaders,
+        Self::Music,
+        Self::Rooms,
+    ];
+}
+
+impl std::fmt::Display for ContentFacet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::All => "All mods",
+                Self::Items => "Adds items",
+                Self::Trinkets => "Adds trinkets",
+                Self::Cards => "Adds cards",
+                Self::Characters => "Adds characters",
+                Self::Lua => "Has Lua code",
+                Self::Shaders => "Has shaders",
+                Self::Music => "Has music",
+                Self::Rooms => "Has room files",
+            }
+        )
+    }
+}
+
+/// Entries of two enabled mods sharing a name or an explicit id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentCollision {
+    pub mods: (ModKey, ModKey),
+    pub file: &'static str,
+    pub description: String,
+}
+
+pub fn find_content_collisions(mod_list: &[Mod]) -> Vec<ContentCollision> {
+    let mut owners: BTreeMap<(&'static str, String, String), Vec<ModKey>> = BTreeMap::new();
+    for m in mod_list.iter().filter(|m| m.enabled()) {
+        for entry in &m.content.entries {
+            let group = entry.group().to_string();
+            if let Some(name) = &entry.name {
+                let key = (entry.file, group.clone(), format!("name \"{name}\""));
+                owners.entry(key).or_default().push(m.key());
+            }
+            if let Some(id) = &entry.id {
+                let key = (entry.file, group, format!("id {id}"));
+                owners.entry(key).or_default().push(m.key());
+            }
+        }
+    }
+
+    let mut collisions = Vec::new();
+    for ((file, group, key), mut ids) in owners {
+        ids.dedup();
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                collisions.push(ContentCollision {
+                    mods: (a.clone(), b.clone()),
+                    file,
+                    description: format!("{group} {key}"),
+                });
+            }
+        }
+    }
+    collisions
+}
+
+/// Parses the entries directly under the root element of a content file.
+pub fn parse_content(file: &'static str, contents: &str) -> Vec<ContentEntry> {
+    let mut reader = Reader::from_str(contents);
+    reader.trim_text(true);
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    loop {
+        let (element, is_empty) = match reader.read_event() {
+            Ok(Event::Start(e)) => (e, false),
+            Ok(Event::Empty(e)) => (e, true),
+            Ok(Event::End(_)) => {
+                depth -= 1;
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        if depth == 1 {
+            let attribute = |key: &str| {
+                element
+                    .try_get_attribute(key)
+                    .ok()
+                    .flatten()
+                    .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+            };
+            let id = attribute("id").map(|id| match file {
+                "entities2.xml" => format!(
+                    "{id}.{}.{}",
+                    attribute("variant").unwrap_or("0".to_string()),
+                    attribute("subtype").unwrap_or("0".to_string())
+                ),
+                _ => id,
+            });
+            entries.push(ContentEntry {
+                file,
+                tag: String::from_utf8_lossy(element.name().as_ref()).to_lowercase(),
+                name: attribute("name"),
+                id,
+            });
+        }
+        if !is_empty {
+            depth += 1;
+        }
+    }
+    entries
+}
+
+/// Finds a file in a directory, ignoring the case of its name.
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| {
+            p.file_name()
+                .is_some_and(|f| f.to_string_lossy().eq_ignore_ascii_case(name))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{types::{DisableStrategy, Mod}, util::test::{write_mod, TempDir}};
+
+    use super::{find_content_collisions, parse_content, ContentFacet, ContentSummary};
+
+    #[test]
+    fn parse_items() {
+        let entries = parse_content("items.xml", r#"<items gfxroot="gfx/items/" version="1">
+    <passive name="Sad Onion Plus" description="Tears up" gfx="onion.png" />
+    <active name="Big Button" id="800" maxcharges="6">
+        <cache>damage</cache>
+    </active>
+    <trinket name="Lucky Rock" gfx="rock.png" />
+</items>"#);
+        assert_eq!(entries.len(), 3, "Only the entries under the root element should be found");
+        assert_eq!(entries[0].name.as_deref(), Some("Sad Onion Plus"));
+        assert_eq!(entries[1].id.as_deref(), Some("800"), "Explicit ids should be kept");
+        assert_eq!(entries[2].tag, "trinket");
+    }
+
+    #[test]
+    fn parse_entities() {
+        let entries = parse_content("entities2.xml", r#"<entities anm2root="gfx/" version="5">
+    <entity name="Blue Fly" id="18" variant="4000" anm2path="fly.anm2" />
+</entities>"#);
+        assert_eq!(entries[0].id.as_deref(), Some("18.4000.0"), "Entity ids should include the variant and subtype");
+    }
+
+    #[test]
+    fn summary() {
+        let dir = TempDir::new("content");
+        fs::create_dir_all(dir.join("content")).unwrap();
+        fs::write(dir.join("content/Items.xml"), r#"<items><passive name="A" /><active name="B" /><trinket name="C" /></items>"#).unwrap();
+        fs::write(dir.join("content/pocketitems.xml"), r#"<pocketitems><card name="D" /><rune name="E" /></pocketitems>"#).unwrap();
+        let files = ["content/items.xml", "content/pocketitems.xml", "main.lua", "resources/music/theme.ogg", "resources/rooms/01.special rooms.stb"].map(String::from);
+        let summary = ContentSummary::from_files(&dir, &files);
+        assert_eq!(summary.describe(), ["2 items", "1 trinket", "2 cards and runes", "Lua code", "music", "room files"], "Content file names should be matched ignoring case");
+        assert_eq!(summary.counts(), [("items.xml", 3), ("pocketitems.xml", 2)]);
+        let facets: Vec<ContentFacet> = ContentFacet::ALL.into_iter().filter(|f| summary.has_facet(*f)).collect();
+        assert_eq!(facets, [ContentFacet::All, ContentFacet::Items, ContentFacet::Trinkets, ContentFacet::Cards, ContentFacet::Lua, ContentFacet::Music, ContentFacet::Rooms]);
+        assert!(ContentSummary::default().has_facet(ContentFacet::All), "Every mod should be shown when not filtering");
+        assert!(!ContentSummary::default().has_facet(ContentFacet::Lua));
+    }
+
+    #[test]
+    fn collisions() {
+        let dir = TempDir::new("collisions");
+        let mods_path = dir.join("mods");
+        for (folder, items) in [("a", r#"<passive name="Onion" id="800" /><trinket name="Rock" />"#), ("b", r#"<active name="Onion" /><trinket name="Pebble" />"#), ("c", r#"<passive id="800" />"#), ("d", r#"<passive name="Onion" />"#)] {
+            let path = write_mod(&mods_path, folder, "");
+            fs::create_dir_all(path.join("content")).unwrap();
+            fs::write(path.join("content/items.xml"), format!("<items>{items}</items>")).unwrap();
+        }
+        fs::write(mods_path.join("d/disable.it"), "").unwrap();
+        let mut mod_list = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        mod_list.sort_by(|a, b| a.path.cmp(&b.path));
+        let collisions: Vec<(String, String, String)> = find_content_collisions(&mod_list).into_iter().map(|c| (c.mods.0.to_string(), c.mods.1.to_string(), c.description)).collect();
+        assert_eq!(collisions, vec![
+            ("a".to_string(), "c".to_string(), "item id 800".to_string()),
+            ("a".to_string(), "b".to_string(), "item name \"Onion\"".to_string()),
+        ], "Passive and active items should share names, and disabled mods should be skipped");
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    types::ModMetadata,
+    util::{find_disable_markers, list_files},
+    validate::{folder_matches_directory, Severity},
+};
+
+/// A fix that cannot lose anything and can be applied without asking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SafeFix {
+    RemoveEmptyFolder(PathBuf),
+    RenameFile { from: PathBuf, to: PathBuf },
+    RemoveFile(PathBuf),
+}
+
+impl SafeFix {
+    pub fn apply(&self) -> Result<()> {
+        match self {
+            Self::RemoveEmptyFolder(path) => {
+                // Something may have been added since the scan
+                if !list_files(path).is_empty() {
+                    return Err(anyhow!("{} is no longer empty", path.display()));
+                }
+                fs::remove_dir_all(path)?;
+            }
+            Self::RenameFile { from, to } => {
+                // Going through a temporary name, case-only renames are no-ops on some file systems
+                let temp = from.with_extension("renaming");
+                fs::rename(from, &temp)?;
+                fs::rename(temp, to)?;
+            }
+            Self::RemoveFile(path) => fs::remove_file(path)?,
+        }
+        Ok(())
+    }
+}
+
+/// A problem found in the mods folder.
+#[derive(Debug, Clone)]
+pub struct Diagnosis {
+    pub severity: Severity,
+    /// Name of the folder in the mods folder the problem is in.
+    pub folder: String,
+    pub message: String,
+    pub suggestion: String,
+    pub fix: Option<SafeFix>,
+}
+
+/// Scans a mods folder for broken state the game or the manager would trip over.
+pub fn diagnose(mods_path: &Path) -> Result<Vec<Diagnosis>> {
+    if mods_path.as_os_str().is_empty() || mods_path.is_relative() {
+        return Err(anyhow!("Invalid mod path set!"));
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(mods_path)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    entries.sort();
+
+    let mut diagnoses = Vec::new();
+    let mut ids: HashMap<u64, Vec<String>> = HashMap::new();
+    for path in entries {
+        let folder = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut add = |severity, message: String, suggestion: String, fix| {
+            diagnoses.push(Diagnosis {
+                severity,
+                folder: folder.clone(),
+                message,
+                suggestion,
+                fix,
+            })
+        };
+
+        if list_files(&path).is_empty() {
+            add(
+                Severity::Warning,
+                "The folder is empty".to_string(),
+                "Remove the folder".to_string(),
+                Some(SafeFix::RemoveEmptyFolder(path.clone())),
+            );
+            continue;
+        }
+
+        let markers = find_disable_markers(&path);
+        let has_marker = markers.iter().any(|p| p.ends_with("disable.it"));
+        for marker in markers.iter().filter(|p| !p.ends_with("disable.it")) {
+            let name = marker.file_name().unwrap_or_default().to_string_lossy();
+            if has_marker {
+                add(
+                    Severity::Warning,
+                    format!("{name} duplicates disable.it"),
+                    format!("Remove {name}"),
+                    Some(SafeFix::RemoveFile(marker.clone())),
+                );
+            } else {
+                add(
+                    Severity::Warning,
+                    format!("{name} is not spelled disable.it"),
+                    format!("Rename {name} to disable.it"),
+                    Some(SafeFix::RenameFile {
+                        from: marker.clone(),
+                        to: path.join("disable.it"),
+                    }),
+                );
+            }
+        }
+
+        let metadata_path = path.join("metadata.xml");
+        if !metadata_path.is_file() {
+            let nested = fs::read_dir(&path)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .find(|e| e.path().join("metadata.xml").is_file());
+            match nested {
+                Some(nested) => add(
+                    Severity::Error,
+                    format!(
+                        "The mod is nested one folder too deep, in {}",
+                        nested.file_name().to_string_lossy()
+                    ),
+                    "Move the contents of the inner folder up one level".to_string(),
+                    None,
+                ),
+                None => add(
+                    Severity::Error,
+                    "metadata.xml is missing".to_string(),
+                    "Reinstall the mod, or uninstall it if it is left over".to_string(),
+                    None,
+                ),
+            }
+            continue;
+        }
+
+        let metadata = fs::read_to_string(&metadata_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| Ok(quick_xml::de::from_str::<ModMetadata>(&c)?));
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                add(
+                    Severity::Error,
+                    format!("metadata.xml cannot be read: {e}"),
+                    "Fix metadata.xml by hand, or reinstall the mod".to_string(),
+                    None,
+                );
+                continue;
+            }
+        };
+        let id = (metadata.id != 0).then(|| metadata.id.to_string());
+        if !folder_matches_directory(&folder, metadata.directory.trim(), id.as_deref()) {
+            add(
+                Severity::Warning,
+                format!(
+                    "The folder does not match the directory \"{}\" in metadata.xml",
+                    metadata.directory.trim()
+                ),
+                "Rename the folder or change the directory in metadata.xml".to_string(),
+                None,
+            );
+        }
+        if metadata.id != 0 {
+            ids.entry(metadata.id).or_default().push(folder.clone());
+        }
+    }
+
+    let mut duplicates: Vec<_> = ids.into_iter().filter(|(_, f)| f.len() > 1).collect();
+    duplicates.sort();
+    for (id, folders) in duplicates {
+        diagnoses.push(Diagnosis {
+            severity: Severity::Error,
+            folder: folders[0].clone(),
+            message: format!(
+                "The workshop id {id} is shared with {}",
+                folders[1..].join(", ")
+            ),
+            suggestion: "Uninstall all but one of these folders".to_string(),
+            fix: None,
+        });
+    }
+    Ok(diagnoses)
+}
+
+/// Applies every safe fix, returning how many were applied.
+pub fn apply_safe_fixes(diagnoses: &[Diagnosis]) -> Result<usize> {
+    let mut applied = 0;
+    for fix in diagnoses.iter().filter_map(|d| d.fix.as_ref()) {
+        fix.apply()?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{util::test::TempDir, validate::Severity};
+
+    use super::{apply_safe_fixes, diagnose};
+
+    const METADATA: &str = "<metadata><name>A</name><directory>a</directory><id>5</id><description/><version>1</version><visibility>Public</visibility></metadata>";
+
+    #[test]
+    fn diagnose_mods_folder() {
+        let dir = TempDir::new("doctor");
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::create_dir_all(dir.join("a_5")).unwrap();
+        fs::write(dir.join("a_5/metadata.xml"), METADATA).unwrap();
+        fs::write(dir.join("a_5/DISABLE.IT"), "").unwrap();
+        fs::create_dir_all(dir.join("copy")).unwrap();
+        fs::write(dir.join("copy/metadata.xml"), METADATA).unwrap();
+        fs::create_dir_all(dir.join("broken")).unwrap();
+        fs::write(dir.join("broken/metadata.xml"), "<metadata><name>").unwrap();
+        fs::create_dir_all(dir.join("wrapped/inner")).unwrap();
+        fs::write(dir.join("wrapped/inner/metadata.xml"), METADATA).unwrap();
+
+        let diagnoses = diagnose(&dir).unwrap();
+        let find = |folder: &str| diagnoses.iter().filter(|d| d.folder == folder).collect::<Vec<_>>();
+        assert!(find("empty")[0].fix.is_some(), "Empty folders can be removed safely");
+        assert!(find("a_5")[0].fix.is_some(), "A wrongly spelled disable.it can be renamed safely");
+        assert!(find("broken")[0].message.contains("cannot be read"));
+        assert!(find("wrapped")[0].message.contains("nested"));
+        assert!(find("copy").iter().any(|d| d.message.contains("does not match")), "The folder should match the directory");
+        assert!(find("a_5").iter().any(|d| d.severity == Severity::Error && d.message.contains("shared with copy")), "Duplicate ids should be reported");
+
+        assert_eq!(apply_safe_fixes(&diagnoses).unwrap(), 2);
+        assert!(!dir.join("empty").exists());
+        let names: Vec<_> = fs::read_dir(dir.join("a_5")).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert!(names.iter().any(|n| n == "disable.it"), "The marker should be renamed: {names:?}");
+        assert!(diagnose(&dir).unwrap().iter().all(|d| d.fix.is_none()), "Nothing safe should be left to fix");
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::{archive::compress_dir, types::Mod};
+
+/// Files never worth shipping, whatever the configured patterns are.
+const ALWAYS_IGNORED: [&str; 2] = [".git", "disable.it"];
+
+/// Ignore patterns used when none are configured: editor backups and OS clutter.
+pub fn default_export_ignore() -> Vec<String> {
+    [
+        "*.bak",
+        "*~",
+        "*.orig",
+        "*.tmp",
+        ".vscode",
+        ".idea",
+        ".DS_Store",
+        "Thumbs.db",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+/// Name of the zip a mod is exported to, built from its name and version.
+pub fn export_file_name(m: &Mod) -> String {
+    let name = format!("{} {}", m.metadata.name.trim(), m.metadata.version.trim());
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '.' || c == '-' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{name}.zip")
+}
+
+/// Zips a mod folder, wrapped in a folder named after its directory so that the archive can be
+/// extracted straight into the mods folder.
+pub fn export_mod(m: &Mod, target: &Path, patterns: &[String]) -> Result<PathBuf> {
+    // An archive written inside the mod folder would end up containing itself
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if parent.canonicalize()?.starts_with(m.path.canonicalize()?) {
+        return Err(anyhow!(
+            "Cannot export mod: the archive cannot be saved inside the mod folder"
+        ));
+    }
+    let prefix = format!("{}/", m.metadata.directory.trim());
+    compress_dir(&m.path, target, &prefix, &|path| is_ignored(path, patterns))?;
+    Ok(target.to_path_buf())
+}
+
+/// Whether a path relative to the mod folder matches an ignore pattern. Patterns match either
+/// the whole path or any of its components.
+pub fn is_ignored(path: &str, patterns: &[String]) -> bool {
+    let patterns = ALWAYS_IGNORED
+        .iter()
+        .copied()
+        .chain(patterns.iter().map(|p| p.trim()))
+        .filter(|p| !p.is_empty());
+    for pattern in patterns {
+        if matches_pattern(pattern, path) || path.split('/').any(|c| matches_pattern(pattern, c)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Matches a glob pattern supporting `*` and `?`, ignoring case like the file systems of most
+/// players do.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{types::Mod, util::test::{write_mod, TempDir}};
+
+    use super::{default_export_ignore, export_mod, is_ignored, matches_pattern};
+
+    #[test]
+    fn glob_patterns() {
+        assert!(matches_pattern("*.bak", "main.lua.bak"));
+        assert!(matches_pattern("*~", "items.xml~"));
+        assert!(matches_pattern("room?.stb", "Room1.stb"), "Patterns should ignore case");
+        assert!(matches_pattern("a*b*c", "axxbyyc"));
+        assert!(!matches_pattern("*.bak", "main.lua"));
+        assert!(!matches_pattern("a*b", "abc"));
+    }
+
+    #[test]
+    fn ignored_files() {
+        let patterns = default_export_ignore();
+        assert!(is_ignored(".git/config", &patterns), ".git should always be ignored");
+        assert!(is_ignored("disable.it", &[]), "The disable marker should always be ignored");
+        assert!(is_ignored("resources/gfx/sprite.png.bak", &patterns));
+        assert!(is_ignored(".vscode/settings.json", &patterns), "Ignored folders should be skipped entirely");
+        assert!(!is_ignored("resources/gfx/sprite.png", &patterns));
+        assert!(is_ignored("notes/todo.txt", &["notes".to_string()]), "Configured patterns should be used");
+    }
+
+    #[test]
+    fn export_target() {
+        let dir = TempDir::new("export");
+        fs::create_dir_all(write_mod(&dir, "a", "").join("content")).unwrap();
+        let m = Mod::from_path(dir.join("a")).unwrap();
+        assert!(export_mod(&m, &dir.join("a/content/a.zip"), &[]).is_err(), "Archives should not be saved inside the mod folder");
+        assert!(!dir.join("a/content/a.zip").exists());
+        export_mod(&m, &dir.join("a.zip"), &[]).unwrap();
+        assert!(dir.join("a.zip").exists());
+    }
+}
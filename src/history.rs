@@ -0,0 +1,379 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::{Mod, ModKey},
+    util::{get_config_dir, is_disable_marker, list_files},
+};
+
+/// Number of scans kept in the history file.
+const KEPT_SCANS: usize = 50;
+/// Files above this size are fingerprinted by their size only, to keep scans fast.
+const HASHED_FILE_SIZE: u64 = 1024 * 1024;
+
+/// State of a mod folder at the time of a scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModSnapshot {
+    pub id: ModKey,
+    pub name: String,
+    pub version: String,
+    pub fingerprint: u64,
+}
+
+impl ModSnapshot {
+    pub fn new(m: &Mod, fingerprint: u64) -> Self {
+        Self {
+            id: m.key(),
+            name: m.metadata.name.clone(),
+            version: m.metadata.version.clone(),
+            fingerprint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scan {
+    /// Unix timestamp of the scan.
+    pub time: u64,
+    pub mods: Vec<ModSnapshot>,
+}
+
+/// Fingerprint of a mod folder, with a hash of the file sizes and modification times it was
+/// computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFingerprint {
+    stamp: u64,
+    fingerprint: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    VersionChanged { from: String, to: String },
+    FilesChanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModChange {
+    pub id: ModKey,
+    pub name: String,
+    pub kind: ChangeKind,
+}
+
+impl std::fmt::Display for ModChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ChangeKind::Added => write!(f, "{} was added", self.name),
+            ChangeKind::Removed => write!(f, "{} was removed", self.name),
+            ChangeKind::VersionChanged { from, to } => {
+                write!(f, "{} was updated from version {from} to {to}", self.name)
+            }
+            ChangeKind::FilesChanged => {
+                write!(f, "{} changed files without a new version", self.name)
+            }
+        }
+    }
+}
+
+/// Changes between two scans, with the time of the older one.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeReport {
+    pub since: Option<u64>,
+    pub changes: Vec<ModChange>,
+}
+
+/// Mod folders of a game instance as they were on previous scans.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanHistory {
+    #[serde(skip)]
+    instance: u32,
+    pub scans: Vec<Scan>,
+    /// Fingerprints of the mod folders, so that unchanged folders are not hashed again.
+    #[serde(default)]
+    fingerprints: HashMap<PathBuf, CachedFingerprint>,
+}
+
+impl ScanHistory {
+    fn get_history_file(instance: u32) -> String {
+        match instance {
+            0 => "history.json".to_string(),
+            id => format!("history_{id}.json"),
+        }
+    }
+
+    pub fn load(instance: u32) -> Result<Self> {
+        if let Some(path) = get_config_dir() {
+            let history_path = path.join(Self::get_history_file(instance));
+            let history_contents = fs::read_to_string(history_path)?;
+            let history: Self = serde_json::from_str(&history_contents)?;
+            Ok(Self {
+                instance,
+                ..history
+            })
+        } else {
+            Err(anyhow!(
+                "Cannot load scan history: directory somehow missing"
+            ))
+        }
+    }
+
+    pub fn load_or_default(instance: u32) -> Self {
+        Self::load(instance).unwrap_or(Self {
+            instance,
+            ..Default::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = get_config_dir() {
+            if !path.exists() {
+                fs::create_dir(&path)?;
+            }
+            let history_path = path.join(Self::get_history_file(self.instance));
+            let history_contents = serde_json::to_string(self)?;
+            fs::write(history_path, history_contents)?;
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Cannot save scan history: directory somehow missing"
+            ))
+        }
+    }
+
+    /// Takes a snapshot of the mod folders, only hashing the files of folders that changed since
+    /// they were last hashed.
+    pub fn scan(&mut self, mod_list: &[Mod]) -> Scan {
+        let mut fingerprints = HashMap::new();
+        let mods = mod_list
+            .iter()
+            .map(|m| {
+                let files = fingerprinted_files(&m.path);
+                let stamp = stamp(&files);
+                let cached = self
+                    .fingerprints
+                    .remove(&m.path)
+                    .filter(|c| c.stamp == stamp);
+                let cached = cached.unwrap_or_else(|| CachedFingerprint {
+                    stamp,
+                    fingerprint: hash_files(files),
+                });
+                let snapshot = ModSnapshot::new(m, cached.fingerprint);
+                fingerprints.insert(m.path.clone(), cached);
+                snapshot
+            })
+            .collect();
+        self.fingerprints = fingerprints;
+        Scan {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            mods,
+        }
+    }
+
+    /// Records a scan if anything changed since the last one, and returns the changes. Scanning
+    /// the same folders twice reports nothing the second time.
+    pub fn record(&mut self, scan: Scan) -> ChangeReport {
+        let unchanged = self
+            .scans
+            .last()
+            .is_some_and(|last| same_mods(&last.mods, &scan.mods));
+        if unchanged {
+            return ChangeReport::default();
+        }
+        self.scans.push(scan);
+        if self.scans.len() > KEPT_SCANS {
+            self.scans.drain(..self.scans.len() - KEPT_SCANS);
+        }
+        match &self.scans[..] {
+            [.., previous, latest] => ChangeReport {
+                since: Some(previous.time),
+                changes: compare(&previous.mods, &latest.mods),
+            },
+            _ => ChangeReport::default(),
+        }
+    }
+}
+
+fn same_mods(a: &[ModSnapshot], b: &[ModSnapshot]) -> bool {
+    a.len() == b.len() && a.iter().all(|m| b.contains(m))
+}
+
+pub fn compare(old: &[ModSnapshot], new: &[ModSnapshot]) -> Vec<ModChange> {
+    let mut changes = Vec::new();
+    for m in new {
+        let kind = match old.iter().find(|o| o.id == m.id) {
+            None => ChangeKind::Added,
+            Some(o) if o.version != m.version => ChangeKind::VersionChanged {
+                from: o.version.clone(),
+                to: m.version.clone(),
+            },
+            Some(o) if o.fingerprint != m.fingerprint => ChangeKind::FilesChanged,
+            Some(_) => continue,
+        };
+        changes.push(ModChange {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            kind,
+        });
+    }
+    for o in old.iter().filter(|o| !new.iter().any(|m| m.id == o.id)) {
+        changes.push(ModChange {
+            id: o.id.clone(),
+            name: o.name.clone(),
+            kind: ChangeKind::Removed,
+        });
+    }
+    changes
+}
+
+/// A file of a mod folder, with its path relative to the folder, size and modification time.
+struct FileEntry {
+    relative: String,
+    size: u64,
+    modified: u128,
+    path: PathBuf,
+}
+
+/// Lists the files of a mod folder, leaving out the marker that disables it.
+fn fingerprinted_files(path: &Path) -> Vec<FileEntry> {
+    let mut files: Vec<FileEntry> = list_files(path)
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file
+                .strip_prefix(path)
+                .ok()?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let metadata = fs::metadata(&file).ok()?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos());
+            Some(FileEntry {
+                relative,
+                size: metadata.len(),
+                modified,
+                path: file,
+            })
+        })
+        .filter(|f| !is_disable_marker(&f.relative))
+        .collect();
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    files
+}
+
+/// FNV-1a, which unlike the standard hasher is stable between builds.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Hashes the names, sizes and modification times of files, to tell when they need hashing again.
+fn stamp(files: &[FileEntry]) -> u64 {
+    let mut hash = Fnv::new();
+    for file in files {
+        hash.feed(file.relative.as_bytes());
+        hash.feed(&file.size.to_le_bytes());
+        hash.feed(&file.modified.to_le_bytes());
+    }
+    hash.0
+}
+
+fn hash_files(files: Vec<FileEntry>) -> u64 {
+    let mut hash = Fnv::new();
+    for file in files {
+        hash.feed(file.relative.as_bytes());
+        hash.feed(&file.size.to_le_bytes());
+        if file.size <= HASHED_FILE_SIZE {
+            hash.feed(&fs::read(file.path).unwrap_or_default());
+        }
+    }
+    hash.0
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use crate::{types::{Mod, ModKey}, util::test::{write_mod, TempDir}};
+
+    use super::{compare, fingerprinted_files, hash_files, ChangeKind, ModSnapshot, Scan, ScanHistory};
+
+    fn fingerprint(path: &Path) -> u64 {
+        hash_files(fingerprinted_files(path))
+    }
+
+    fn snapshot(id: u64, version: &str, fingerprint: u64) -> ModSnapshot {
+        ModSnapshot { id: id.into(), name: format!("Mod {id}"), version: version.to_string(), fingerprint }
+    }
+
+    #[test]
+    fn compare_scans() {
+        let old = vec![snapshot(1, "1.0", 10), snapshot(2, "1.0", 20), snapshot(3, "1.0", 30)];
+        let new = vec![snapshot(1, "1.1", 11), snapshot(2, "1.0", 21), snapshot(4, "1.0", 40)];
+        let kinds: Vec<(ModKey, ChangeKind)> = compare(&old, &new).into_iter().map(|c| (c.id, c.kind)).collect();
+        assert_eq!(kinds, vec![
+            (1.into(), ChangeKind::VersionChanged { from: "1.0".to_string(), to: "1.1".to_string() }),
+            (2.into(), ChangeKind::FilesChanged),
+            (4.into(), ChangeKind::Added),
+            (3.into(), ChangeKind::Removed),
+        ]);
+    }
+
+    #[test]
+    fn record_scans() {
+        let mut history = ScanHistory::default();
+        assert!(history.record(Scan { time: 1, mods: vec![snapshot(1, "1.0", 10)] }).changes.is_empty(), "The first scan has nothing to compare to");
+        let report = history.record(Scan { time: 2, mods: vec![snapshot(1, "1.1", 10)] });
+        assert_eq!((report.since, report.changes.len()), (Some(1), 1));
+        let report = history.record(Scan { time: 3, mods: vec![snapshot(1, "1.1", 10)] });
+        assert_eq!(history.scans.len(), 2, "Identical scans should not be recorded");
+        assert!(report.changes.is_empty(), "Changes should only be reported by the scan that found them");
+    }
+
+    #[test]
+    fn fingerprint_ignores_disable_marker() {
+        let dir = TempDir::new("history");
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(dir.join("main.lua"), "print('hi')").unwrap();
+        let before = fingerprint(&dir);
+        fs::write(dir.join("disable.it"), "").unwrap();
+        assert_eq!(fingerprint(&dir), before, "Disabling a mod should not change its fingerprint");
+        fs::write(dir.join("scripts/extra.lua"), "").unwrap();
+        assert_ne!(fingerprint(&dir), before, "Adding a file should change the fingerprint");
+    }
+
+    #[test]
+    fn scan_caches_fingerprints() {
+        let temp = TempDir::new("history-cache");
+        let dir = write_mod(&temp, "a", "");
+        let mod_list = vec![Mod::from_path(dir.clone()).unwrap()];
+        let mut history = ScanHistory::default();
+        assert_eq!(history.scan(&mod_list).mods[0].fingerprint, fingerprint(&dir));
+
+        history.fingerprints.get_mut(&dir).unwrap().fingerprint = 1;
+        assert_eq!(history.scan(&mod_list).mods[0].fingerprint, 1, "Unchanged folders should not be hashed again");
+        fs::write(dir.join("main.lua"), "print('hi')").unwrap();
+        assert_eq!(history.scan(&mod_list).mods[0].fingerprint, fingerprint(&dir), "Changed folders should be hashed again");
+    }
+}
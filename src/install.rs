@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    archive::extract_zip,
+    types::Mod,
+    util::{copy_dir, list_files},
+};
+
+/// Start of the names of the temporary folders zips are extracted to, followed by the process id
+/// and a timestamp.
+const STAGING_PREFIX: &str = "icy-isaac-install-";
+
+/// A mod ready to be copied into the mods folder, once the user confirms it.
+#[derive(Debug, Clone)]
+pub struct InstallPlan {
+    pub source: PathBuf,
+    /// Folder holding `metadata.xml`, inside the source or its extracted copy.
+    pub root: PathBuf,
+    /// Temporary folder a zip was extracted to.
+    pub staging: Option<PathBuf>,
+    pub name: String,
+    pub id: u64,
+    pub version: String,
+    /// Name of the folder the mod is installed as.
+    pub folder: String,
+}
+
+impl InstallPlan {
+    /// Validates a zip or a mod folder and checks it does not clash with installed mods.
+    pub fn new(source: &Path, mods_path: &Path, mod_list: &[Mod]) -> Result<Self> {
+        if mods_path.as_os_str().is_empty() || mods_path.is_relative() {
+            return Err(anyhow!("Invalid mod path set!"));
+        }
+        let staging = if source.is_dir() {
+            None
+        } else if source
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+        {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+            let staging = std::env::temp_dir()
+                .join(format!("{STAGING_PREFIX}{}-{nanos}", std::process::id()));
+            if let Err(e) = extract_zip(source, &staging) {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+            Some(staging)
+        } else {
+            return Err(anyhow!("Only zip files and folders can be installed"));
+        };
+
+        let plan = Self::check(
+            source,
+            staging.as_deref().unwrap_or(source),
+            mods_path,
+            mod_list,
+        );
+        if let (Err(_), Some(staging)) = (&plan, &staging) {
+            let _ = fs::remove_dir_all(staging);
+        }
+        Ok(Self { staging, ..plan? })
+    }
+
+    fn check(source: &Path, dir: &Path, mods_path: &Path, mod_list: &[Mod]) -> Result<Self> {
+        let root = find_mod_root(dir)?;
+        let m = Mod::from_path(root.clone())
+            .map_err(|e| anyhow!("Invalid metadata.xml in {}: {e}", source.display()))?;
+        // Mods that were never uploaded all share id 0
+        let same_id = mod_list
+            .iter()
+            .find(|o| m.metadata.id != 0 && o.metadata.id == m.metadata.id);
+        if let Some(other) = same_id {
+            return Err(anyhow!(
+                "A mod with id {} is already installed: {}",
+                m.metadata.id,
+                other.metadata.name
+            ));
+        }
+        let folder = m.metadata.directory.trim().to_string();
+        if folder.is_empty() || folder.contains(['/', '\\']) || folder.starts_with('.') {
+            return Err(anyhow!("Invalid mod directory name: {folder}"));
+        }
+        let clashes = mod_list.iter().any(|o| {
+            o.metadata.directory.eq_ignore_ascii_case(&folder)
+                || o.path
+                    .file_name()
+                    .is_some_and(|f| f.to_string_lossy().eq_ignore_ascii_case(&folder))
+        });
+        if clashes || mods_path.join(&folder).exists() {
+            return Err(anyhow!(
+                "A mod is already installed in a folder named {folder}"
+            ));
+        }
+        Ok(Self {
+            source: source.to_path_buf(),
+            root,
+            staging: None,
+            name: m.metadata.name,
+            id: m.metadata.id,
+            version: m.metadata.version,
+            folder,
+        })
+    }
+
+    /// Copies the mod into the mods folder and returns where it was installed.
+    pub fn apply(self, mods_path: &Path) -> Result<PathBuf> {
+        let target = mods_path.join(&self.folder);
+        let result = copy_dir(&self.root, &target);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&target);
+        }
+        self.cancel();
+        result?;
+        Ok(target)
+    }
+
+    /// Removes the extracted copy of a zip, if there is one.
+    pub fn cancel(self) {
+        if let Some(staging) = self.staging {
+            let _ = fs::remove_dir_all(staging);
+        }
+    }
+}
+
+/// Removes the extracted zips left behind when the manager was closed on the install screen.
+pub fn clean_stale_staging() {
+    clean_staging(&std::env::temp_dir(), Duration::from_secs(60 * 60));
+}
+
+/// Removes the staging folders of other processes older than the given age, leaving the ones
+/// another running manager may still use.
+fn clean_staging(dir: &Path, age: Duration) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some((pid, nanos)) = name
+            .strip_prefix(STAGING_PREFIX)
+            .and_then(|rest| rest.split_once('-'))
+        else {
+            continue;
+        };
+        let Ok(pid) = pid.parse::<u32>() else {
+            continue;
+        };
+        if pid == std::process::id() || nanos.parse::<u32>().is_err() {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|t| t.elapsed().is_ok_and(|e| e >= age));
+        if stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Finds the folder holding `metadata.xml`, which zips often wrap in an extra folder.
+pub fn find_mod_root(dir: &Path) -> Result<PathBuf> {
+    if dir.join("metadata.xml").is_file() {
+        return Ok(dir.to_path_buf());
+    }
+    let mut roots: Vec<PathBuf> = list_files(dir)
+        .into_iter()
+        // Matched exactly, as the game and the mod list only read this spelling
+        .filter(|f| f.file_name().is_some_and(|n| n == "metadata.xml"))
+        .filter_map(|f| f.parent().map(Path::to_path_buf))
+        .collect();
+    // Only keep the outermost folders, mods may ship example metadata deeper down
+    let depth = roots.iter().map(|r| r.components().count()).min();
+    roots.retain(|r| Some(r.components().count()) == depth);
+    match &roots[..] {
+        [] => Err(anyhow!("No metadata.xml found in {}", dir.display())),
+        [root] if root.components().count() - dir.components().count() <= 2 => Ok(root.clone()),
+        [_] => Err(anyhow!(
+            "metadata.xml is nested too deep in {}",
+            dir.display()
+        )),
+        _ => Err(anyhow!("{} holds several mods", dir.display())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use crate::util::test::TempDir;
+
+    use super::{clean_staging, find_mod_root, InstallPlan};
+
+    const METADATA: &str = "<metadata><name>Test Mod</name><directory>test mod</directory><id>42</id><description>Test</description><version>1.0</version><visibility>Public</visibility></metadata>";
+
+    #[test]
+    fn install_folder() {
+        let dir = TempDir::new("install-test");
+        let source = dir.join("download/Test Mod v1.0");
+        let mods = dir.join("mods");
+        fs::create_dir_all(source.join("resources")).unwrap();
+        fs::create_dir_all(&mods).unwrap();
+        fs::write(source.join("metadata.xml"), METADATA).unwrap();
+        fs::write(source.join("resources/sprite.png"), "png").unwrap();
+
+        assert_eq!(find_mod_root(&dir.join("download")).unwrap(), source, "A wrapper folder should be looked into");
+        let plan = InstallPlan::new(&dir.join("download"), &mods, &[]).unwrap();
+        assert_eq!((plan.id, plan.folder.as_str()), (42, "test mod"));
+        let target = plan.apply(&mods).unwrap();
+        assert!(target.join("resources/sprite.png").exists(), "Every file should be copied");
+        assert!(InstallPlan::new(&dir.join("download"), &mods, &[]).is_err(), "Installing over an existing folder should be refused");
+
+        fs::create_dir_all(dir.join("upper/Mod")).unwrap();
+        fs::write(dir.join("upper/Mod/METADATA.XML"), METADATA).unwrap();
+        assert!(find_mod_root(&dir.join("upper")).is_err(), "Only metadata.xml in lowercase can be loaded");
+    }
+
+    #[test]
+    fn stale_staging() {
+        let dir = TempDir::new("staging");
+        for name in ["icy-isaac-install-1-5", &format!("icy-isaac-install-{}-5", std::process::id()), "icy-isaac-install-test-5", "other"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        clean_staging(&dir, Duration::from_secs(60));
+        assert!(dir.join("icy-isaac-install-1-5").exists(), "Recent folders may belong to another running manager");
+        clean_staging(&dir, Duration::ZERO);
+        assert!(!dir.join("icy-isaac-install-1-5").exists(), "Old folders of other processes should be removed");
+        assert!(dir.join(format!("icy-isaac-install-{}-5", std::process::id())).exists(), "Folders of this process may be in use");
+        assert!(dir.join("icy-isaac-install-test-5").exists() && dir.join("other").exists(), "Other folders should be left alone");
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::types::{Mod, ModKey};
+
+/// Number of messages kept per mod in an error report.
+const LATEST_MESSAGES: usize = 5;
+/// Number of lines kept in memory while tailing the game log.
+const TAIL_LINES: usize = 2000;
+
+/// A Lua error found in the game log, with its stack trace if one was printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogError {
+    pub message: String,
+    pub trace: Vec<String>,
+    /// Name of the mod folder the error comes from, if a path in the error points to one.
+    pub mod_dir: Option<String>,
+}
+
+/// Errors attributed to a single mod.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModErrors {
+    pub mod_id: ModKey,
+    pub count: usize,
+    pub latest: Vec<String>,
+}
+
+/// Errors of a game log, grouped by mod.
+#[derive(Debug, Clone, Default)]
+pub struct LogReport {
+    pub mods: Vec<ModErrors>,
+    pub unattributed: usize,
+}
+
+impl LogReport {
+    pub fn from_file(path: &Path, mod_list: &[Mod]) -> anyhow::Result<Self> {
+        let bytes = fs::read(path)?;
+        let contents = String::from_utf8_lossy(&bytes);
+        Ok(Self::from_errors(&parse_errors(&contents), mod_list))
+    }
+
+    pub fn from_errors(errors: &[LogError], mod_list: &[Mod]) -> Self {
+        let mut mods: HashMap<ModKey, ModErrors> = HashMap::new();
+        let mut unattributed = 0;
+        for error in errors {
+            match error.mod_dir.as_deref().and_then(|d| find_mod(mod_list, d)) {
+                Some(m) => {
+                    let entry = mods.entry(m.key()).or_insert(ModErrors {
+                        mod_id: m.key(),
+                        count: 0,
+                        latest: Vec::new(),
+                    });
+                    entry.count += 1;
+                    entry.latest.push(error.message.clone());
+                    if entry.latest.len() > LATEST_MESSAGES {
+                        entry.latest.remove(0);
+                    }
+                }
+                None => unattributed += 1,
+            }
+        }
+        let mut mods: Vec<ModErrors> = mods.into_values().collect();
+        mods.sort_by(|a, b| b.count.cmp(&a.count).then(a.mod_id.cmp(&b.mod_id)));
+        Self { mods, unattributed }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A line of the game log, with the mod it originates from if one could be found.
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub text: String,
+    pub level: LineLevel,
+    pub mod_id: Option<ModKey>,
+}
+
+impl LogLine {
+    pub fn new(line: &str, mod_list: &[Mod]) -> Self {
+        let text = strip_prefix(line);
+        let level = if is_error(text) {
+            LineLevel::Error
+        } else if line.starts_with("[WARN]") || text.to_lowercase().contains("warning") {
+            LineLevel::Warning
+        } else {
+            LineLevel::Info
+        };
+        let mod_id = find_mod_dir(text)
+            .and_then(|d| find_mod(mod_list, &d))
+            .map(Mod::key);
+        Self {
+            text: text.to_string(),
+            level,
+            mod_id,
+        }
+    }
+}
+
+/// Follows the game log while the game writes to it.
+#[derive(Debug)]
+pub struct LogTail {
+    pub path: PathBuf,
+    pub lines: Vec<LogLine>,
+    offset: u64,
+    partial: String,
+}
+
+impl LogTail {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lines: Vec::new(),
+            offset: 0,
+            partial: String::new(),
+        }
+    }
+
+    /// Reads the lines written since the last poll, returning whether there were any. Starts over
+    /// when the game recreates the log.
+    pub fn poll(&mut self, mod_list: &[Mod]) -> io::Result<bool> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+            self.lines.clear();
+        }
+        if len == self.offset {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+        let complete = match self.partial.rfind('\n') {
+            Some(i) => self.partial.drain(..=i).collect::<String>(),
+            None => return Ok(false),
+        };
+        self.lines
+            .extend(complete.lines().map(|l| LogLine::new(l, mod_list)));
+        if self.lines.len() > TAIL_LINES {
+            self.lines.drain(..self.lines.len() - TAIL_LINES);
+        }
+        Ok(true)
+    }
+}
+
+/// Finds the mod whose folder has the given name, ignoring case like the game does.
+pub fn find_mod<'a>(mod_list: &'a [Mod], dir: &str) -> Option<&'a Mod> {
+    mod_list.iter().find(|m| {
+        m.path
+            .file_name()
+            .is_some_and(|f| f.to_string_lossy().eq_ignore_ascii_case(dir))
+    })
+}
+
+/// Removes the `[INFO] - ` style prefix the game puts on every log line.
+pub fn strip_prefix(line: &str) -> &str {
+    match line.split_once("] - ") {
+        Some((level, rest)) if level.starts_with('[') => rest,
+        _ => line,
+    }
+}
+
+/// Finds the mod folder name in a path like `.../mods/my mod_123456/main.lua:12: ...`.
+pub fn find_mod_dir(line: &str) -> Option<String> {
+    let line = line.replace('\\', "/");
+    let start = line.to_ascii_lowercase().find("/mods/")? + "/mods/".len();
+    let dir = line[start..].split('/').next()?;
+    (!dir.is_empty() && line[start + dir.len()..].starts_with('/')).then(|| dir.to_string())
+}
+
+/// Whether a log line is a Lua error, either reported by the game or with a `file.lua:12:`
+/// style location.
+pub fn is_error(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    if lower.starts_with("error") || lower.contains("lua error") {
+        return true;
+    }
+    lower.match_indices(':').any(|(i, _)| {
+        let before = &lower[..i];
+        let after = &lower[i + 1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        (before.ends_with(".lua") || before.ends_with(']'))
+            && digits > 0
+            && after[digits..].starts_with(':')
+    })
+}
+
+pub fn parse_errors(contents: &str) -> Vec<LogError> {
+    let mut errors: Vec<LogError> = Vec::new();
+    let mut in_trace = false;
+    for line in contents.lines().map(strip_prefix) {
+        if line.trim_start().starts_with("stack traceback") {
+            in_trace = !errors.is_empty();
+            continue;
+        }
+        if in_trace && line.starts_with(|c: char| c.is_whitespace()) {
+            if let Some(error) = errors.last_mut() {
+                error.trace.push(line.trim().to_string());
+                if error.mod_dir.is_none() {
+                    error.mod_dir = find_mod_dir(line);
+                }
+            }
+            continue;
+        }
+        in_trace = false;
+        if is_error(line) {
+            errors.push(LogError {
+                message: line.trim().to_string(),
+                trace: Vec::new(),
+                mod_dir: find_mod_dir(line),
+            });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::util::test::TempDir;
+
+    use super::{find_mod_dir, parse_errors, LineLevel, LogTail};
+
+    #[test]
+    fn mod_dir() {
+        assert_eq!(find_mod_dir("C:/Steam/steamapps/common/The Binding of Isaac Rebirth/mods/my mod_123/main.lua:12: oops").as_deref(), Some("my mod_123"));
+        assert_eq!(
+            find_mod_dir(r"C:\Games\Isaac\Mods\other_456\scripts\init.lua:3: oops").as_deref(),
+            Some("other_456"),
+            "Backslashes and case should not matter"
+        );
+        assert_eq!(find_mod_dir("resources/scripts/main.lua:12: oops"), None);
+    }
+
+    #[test]
+    fn parse_log() {
+        let errors = parse_errors("[INFO] - Lua Debug: nothing wrong here
+[INFO] - Error in \"PostUpdate\" call: C:/Isaac/mods/broken_1/main.lua:42: attempt to index a nil value
+[INFO] - stack traceback:
+[INFO] - 	C:/Isaac/mods/broken_1/main.lua:42: in function <C:/Isaac/mods/broken_1/main.lua:40>
+[INFO] - Lua is resetting!
+[INFO] - [string \"helpers\"]:5: attempt to call a nil value
+");
+        assert_eq!(
+            errors.len(),
+            2,
+            "Debug messages and stack traces should not count as errors"
+        );
+        assert_eq!(errors[0].mod_dir.as_deref(), Some("broken_1"));
+        assert_eq!(
+            errors[0].trace.len(),
+            1,
+            "The stack trace should be attached to the error"
+        );
+        assert_eq!(
+            errors[1].mod_dir, None,
+            "Errors without a mod path cannot be attributed"
+        );
+    }
+
+    #[test]
+    fn tail_log() {
+        let dir = TempDir::new("log");
+        let path = dir.join("log.txt");
+        fs::write(&path, "[INFO] - Lua Debug: hello\n[INFO] - Error in \"PostRender\" call: oops\n[INFO] - half a li").unwrap();
+        let mut tail = LogTail::new(path.clone());
+        assert!(tail.poll(&[]).unwrap(), "The first poll should read the existing lines");
+        assert_eq!(tail.lines.len(), 2, "An unfinished line should not be read yet");
+        assert_eq!(tail.lines[1].level, LineLevel::Error);
+
+        fs::write(&path, "[INFO] - Lua Debug: hello\n[INFO] - Error in \"PostRender\" call: oops\n[INFO] - half a line\n").unwrap();
+        assert!(tail.poll(&[]).unwrap());
+        assert_eq!(tail.lines[2].text, "half a line", "The unfinished line should be completed");
+        assert!(!tail.poll(&[]).unwrap(), "Nothing new should have been read");
+
+        fs::write(&path, "[INFO] - Lua Debug: restarted\n").unwrap();
+        tail.poll(&[]).unwrap();
+        assert_eq!(tail.lines.len(), 1, "A recreated log should be read from the start");
+    }
+}
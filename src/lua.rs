@@ -0,0 +1,292 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::{
+    types::{Mod, ModKey},
+    util::list_files,
+};
+
+/// Globals exposed by well-known libraries, with the name they are usually known by.
+const KNOWN_LIBRARIES: [(&str, &str); 8] = [
+    ("ModConfigMenu", "Mod Config Menu"),
+    ("REPENTOGON", "REPENTOGON"),
+    ("StageAPI", "StageAPI"),
+    ("EID", "External Item Descriptions"),
+    ("MinimapAPI", "Minimap API"),
+    ("CustomHealthAPI", "Custom Health API"),
+    ("TheFuture", "The Future"),
+    ("HPBars", "Enhanced Boss Bars"),
+];
+
+/// A dependency guessed from a mod's Lua code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredDependency {
+    /// Name of the library or mod depended upon.
+    pub name: String,
+    /// What in the code hints at the dependency.
+    pub reason: String,
+    /// Installed mod providing the dependency, if any.
+    pub mod_id: Option<ModKey>,
+}
+
+/// Identifiers, include paths and global definitions found in a mod's Lua files.
+#[derive(Debug, Default)]
+pub struct LuaScan {
+    pub identifiers: HashSet<String>,
+    pub includes: HashSet<String>,
+    pub globals: HashSet<String>,
+}
+
+impl LuaScan {
+    pub fn from_mod(m: &Mod) -> Self {
+        let mut scan = Self::default();
+        for path in list_files(&m.path) {
+            if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("lua"))
+            {
+                if let Ok(source) = fs::read_to_string(&path) {
+                    scan.add_source(&source);
+                }
+            }
+        }
+        scan
+    }
+
+    pub fn add_source(&mut self, source: &str) {
+        for line in strip_comments(source).lines() {
+            for function in ["include", "require"] {
+                if let Some(module) = find_call_argument(line, function) {
+                    self.includes.insert(module);
+                }
+            }
+            let code = strip_strings(line);
+            if let Some(global) = find_global_definition(&code) {
+                self.globals.insert(global);
+            }
+            self.identifiers
+                .extend(identifiers(&code).map(str::to_string));
+        }
+    }
+}
+
+/// Guesses the dependencies of a mod from its Lua code and the other installed mods, given the
+/// scans of every mod of the list.
+pub fn infer_dependencies(
+    index: usize,
+    mod_list: &[Mod],
+    scans: &[LuaScan],
+) -> Vec<InferredDependency> {
+    let (Some(m), Some(scan)) = (mod_list.get(index), scans.get(index)) else {
+        return Vec::new();
+    };
+    let others: Vec<(&Mod, &LuaScan)> = mod_list
+        .iter()
+        .zip(scans)
+        .filter(|(o, _)| o.key() != m.key())
+        .collect();
+    let mut dependencies = Vec::new();
+
+    for (global, name) in KNOWN_LIBRARIES {
+        if scan.identifiers.contains(global) && !scan.globals.contains(global) {
+            let provider = others.iter().find(|(_, s)| s.globals.contains(global));
+            dependencies.push(InferredDependency {
+                name: provider.map_or(name.to_string(), |(o, _)| o.metadata.name.clone()),
+                reason: format!("uses the {global} global"),
+                mod_id: provider.map(|(o, _)| o.key()),
+            });
+        }
+    }
+
+    for (other, other_scan) in &others {
+        if dependencies.iter().any(|d| d.mod_id == Some(other.key())) {
+            continue;
+        }
+        let mut globals: Vec<&String> = other_scan
+            .globals
+            .iter()
+            .filter(|g| scan.identifiers.contains(*g) && !scan.globals.contains(*g))
+            .collect();
+        globals.sort();
+        let include = scan
+            .includes
+            .iter()
+            .find(|i| !module_exists(&m.path, i) && module_exists(&other.path, i));
+        let reason = match (globals.first(), include) {
+            (_, Some(include)) => format!("includes {include}"),
+            (Some(global), None) => format!("uses the {global} global"),
+            (None, None) => continue,
+        };
+        dependencies.push(InferredDependency {
+            name: other.metadata.name.clone(),
+            reason,
+            mod_id: Some(other.key()),
+        });
+    }
+    dependencies
+}
+
+fn module_exists(root: &Path, module: &str) -> bool {
+    let relative = module.trim_end_matches(".lua").replace('.', "/");
+    root.join(format!("{relative}.lua")).is_file()
+}
+
+/// Returns the level of a long bracket like `[==[` at the start of the text, 2 here.
+fn long_bracket_level(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('[')?;
+    let level = rest.chars().take_while(|c| *c == '=').count();
+    rest[level..].starts_with('[').then_some(level)
+}
+
+/// Removes comments from Lua source, and replaces long strings like `[[text]]` with an empty
+/// string. Line breaks are kept, so that the result can still be read line by line.
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut rest = source;
+    // Skips a long bracket of the given level, keeping the line breaks it spans
+    let skip_long = |code: &mut String, text: &str, level: usize| -> usize {
+        let close = format!("]{}]", "=".repeat(level));
+        let end = text.find(&close).map_or(text.len(), |i| i + close.len());
+        code.extend(text[..end].chars().filter(|c| *c == '\n'));
+        end
+    };
+    while let Some(c) = rest.chars().next() {
+        if let Some(comment) = rest.strip_prefix("--") {
+            let skipped = match long_bracket_level(comment) {
+                Some(level) => skip_long(&mut code, comment, level),
+                None => comment.find('\n').unwrap_or(comment.len()),
+            };
+            rest = &comment[skipped..];
+        } else if let Some(level) = long_bracket_level(rest) {
+            code.push_str("\"\"");
+            let skipped = skip_long(&mut code, rest, level);
+            rest = &rest[skipped..];
+        } else if c == '"' || c == '\'' {
+            let end = string_end(rest).unwrap_or(rest.len());
+            code.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else {
+            code.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    code
+}
+
+/// Returns the length of the quoted string at the start of the text, quotes included.
+fn string_end(text: &str) -> Option<usize> {
+    let quote = text.chars().next()?;
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' => return Some(i),
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_strings(line: &str) -> String {
+    let mut code = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            rest = &rest[string_end(rest).unwrap_or(rest.len())..];
+        } else {
+            code.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    code
+}
+
+fn identifiers(code: &str) -> impl Iterator<Item = &str> {
+    code.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|s| s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+}
+
+/// Finds the string argument of calls like `include("scripts.foo")` or `require "foo"`, outside
+/// of strings and other identifiers.
+fn find_call_argument(line: &str, function: &str) -> Option<String> {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            rest = &rest[string_end(rest).unwrap_or(rest.len())..];
+            continue;
+        }
+        let boundary = !line[..line.len() - rest.len()].ends_with(is_identifier);
+        if let Some(after) = rest.strip_prefix(function).filter(|_| boundary) {
+            let after = after.trim_start();
+            let argument = after.strip_prefix('(').unwrap_or(after).trim_start();
+            if argument.starts_with(['"', '\'']) {
+                let end = string_end(argument)?;
+                return argument.get(1..end - 1).map(str::to_string);
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    None
+}
+
+/// Finds top-level assignments like `StageAPI = {}`, ignoring locals and fields.
+fn find_global_definition(code: &str) -> Option<String> {
+    let (name, value) = code.trim_start().split_once('=')?;
+    let name = name.trim_end();
+    if value.starts_with('=') || name.is_empty() || name.contains(|c: char| c.is_whitespace()) {
+        return None;
+    }
+    let is_identifier = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+    is_identifier.then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_call_argument, strip_comments, LuaScan};
+
+    #[test]
+    fn scan_source() {
+        let mut scan = LuaScan::default();
+        scan.add_source(r#"
+local mod = RegisterMod("Test Mod", 1)
+MyLibrary = {}
+include("scripts.helpers")
+-- StageAPI is only mentioned in a comment
+if ModConfigMenu then
+    print("ModConfigMenu found")
+end
+if x == 1 then end
+"#);
+        assert!(scan.globals.contains("MyLibrary"), "Top-level assignments should be global definitions");
+        assert!(!scan.globals.contains("mod"), "Local assignments should not be global definitions");
+        assert!(!scan.globals.contains("x"), "Comparisons should not be global definitions");
+        assert!(scan.includes.contains("scripts.helpers"), "Included modules should be found");
+        assert!(scan.identifiers.contains("ModConfigMenu"), "Referenced globals should be found");
+        assert!(!scan.identifiers.contains("StageAPI"), "Comments should be ignored");
+        assert!(!scan.identifiers.contains("found"), "Strings should be ignored");
+    }
+
+    #[test]
+    fn comments_and_strings() {
+        let code = strip_comments("print(\"--not a comment\") -- a comment\n--[[ StageAPI\nEID ]] x = 1\ny = [==[\nMinimapAPI ]] ]==]");
+        assert_eq!(code, "print(\"--not a comment\") \n\n x = 1\ny = \"\"\n", "Block comments and long strings should be removed, keeping line breaks");
+
+        let mut scan = LuaScan::default();
+        scan.add_source("HPBars = { name = 'it\\'s -- fine', EID = true }\n--[[\nStageAPI.Init()\n]]\nlocal s = [[ MinimapAPI ]]");
+        assert!(scan.identifiers.contains("EID"), "Code after a string holding -- should be read");
+        assert!(!scan.identifiers.contains("fine"), "Escaped quotes should not end strings");
+        assert!(!scan.identifiers.contains("StageAPI"), "Block comments should be ignored");
+        assert!(!scan.identifiers.contains("MinimapAPI"), "Long strings should be ignored");
+    }
+
+    #[test]
+    fn call_arguments() {
+        assert_eq!(find_call_argument("include(\"scripts.a\")", "include").as_deref(), Some("scripts.a"));
+        assert_eq!(find_call_argument("local json = require \"json\"", "require").as_deref(), Some("json"), "Calls without parentheses should be found");
+        assert_eq!(find_call_argument("required(\"a\") xrequire(\"b\")", "require"), None, "Other identifiers should not match");
+        assert_eq!(find_call_argument("print(\"require('a')\")", "require"), None, "Calls in strings should not match");
+    }
+}
@@ -1,9 +1,26 @@
-use iced::{Sandbox, Settings};
+use iced::{Application, Settings};
 
+mod archive;
+mod cli;
+mod conflicts;
+mod content;
+mod doctor;
+mod export;
+mod history;
+mod install;
+mod log;
+mod lua;
 mod manager;
+mod metadata;
+mod quarantine;
+mod rules;
+mod scaffold;
+mod steam;
+mod trash;
 mod types;
 mod ui;
 mod util;
+mod validate;
 
 use crate::ui::ModManager;
 
@@ -11,5 +28,9 @@ const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_command(&args) {
+        std::process::exit(cli::run(&args));
+    }
     ModManager::run(Settings::default())
 }
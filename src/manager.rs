@@ -1,24 +1,37 @@
-use std::{collections::HashMap, fmt::Display, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+};
 
 use anyhow::{anyhow, Result};
 
-use crate::{types::ModProfile, util::get_config_dir};
+use crate::{
+    types::{ModKey, ModProfile},
+    util::get_config_dir,
+};
 
 pub struct ModProfileManager {
+    instance: u32,
     current_profile: i32,
     mod_profiles: HashMap<i32, ModProfile>,
-    pub profile_states: Vec<ModProfileState>,  // These are public because iced
+    pub profile_states: Vec<ModProfileState>, // These are public because iced
     pub current_profile_state: Option<ModProfileState>,
+    pub parent_profile_states: Vec<ModProfileState>,
+    pub current_parent_state: Option<ModProfileState>,
 }
 
 impl Default for ModProfileManager {
     fn default() -> Self {
         let default_profile = Self::get_default_profile();
         Self {
+            instance: 0,
             current_profile: 0,
             mod_profiles: HashMap::new(),
             profile_states: Vec::new(),
             current_profile_state: Some(default_profile.clone()),
+            parent_profile_states: vec![Self::get_no_parent_profile()],
+            current_parent_state: Some(Self::get_no_parent_profile()),
         }
     }
 }
@@ -43,12 +56,28 @@ impl ModProfileManager {
         }
     }
 
-    pub fn load() -> Result<Self> {
+    fn get_no_parent_profile() -> ModProfileState {
+        ModProfileState {
+            id: 0,
+            name: "<none>".to_string(),
+        }
+    }
+
+    /// Name of the file holding the profiles of a game instance.
+    fn get_profiles_file(instance: u32) -> String {
+        match instance {
+            0 => "profiles.json".to_string(),
+            id => format!("profiles_{id}.json"),
+        }
+    }
+
+    pub fn load(instance: u32) -> Result<Self> {
         if let Some(path) = get_config_dir() {
-            let profiles_path = path.join("profiles.json");
+            let profiles_path = path.join(Self::get_profiles_file(instance));
             let profiles_contents = fs::read_to_string(profiles_path)?;
             let mod_profiles = serde_json::from_str(&profiles_contents)?;
             let mut s = Self {
+                instance,
                 current_profile: 0,
                 mod_profiles,
                 ..Default::default()
@@ -62,8 +91,11 @@ impl ModProfileManager {
         }
     }
 
-    pub fn load_or_default() -> Self {
-        Self::load().unwrap_or_default()
+    pub fn load_or_default(instance: u32) -> Self {
+        Self::load(instance).unwrap_or(Self {
+            instance,
+            ..Default::default()
+        })
     }
 
     pub fn save(&self) -> Result<()> {
@@ -71,7 +103,7 @@ impl ModProfileManager {
             if !path.exists() {
                 fs::create_dir(path.clone())?;
             }
-            let profiles_path = path.join("profiles.json");
+            let profiles_path = path.join(Self::get_profiles_file(self.instance));
             let profiles_contents = serde_json::to_string_pretty(&self.mod_profiles)?;
             fs::write(profiles_path, profiles_contents)?;
             Ok(())
@@ -94,16 +126,128 @@ impl ModProfileManager {
         let id = self.get_next_free_id();
         let mod_profile = ModProfile {
             name,
-            enabled_mods: Vec::new(),
+            ..Default::default()
         };
         self.mod_profiles.insert(id, mod_profile);
         self.current_profile = id;
         self.update_state();
     }
 
-    pub fn update_current_profile(&mut self, enabled: Vec<u64>) {
-        if let Some(profile) = self.get_current_profile_mut() {
-            profile.enabled_mods = enabled;
+    pub fn update_current_profile(&mut self, enabled: Vec<ModKey>) {
+        self.set_profile_mods(self.current_profile, enabled);
+    }
+
+    /// Resolves the effective set of enabled mods of the current profile, parents included.
+    pub fn resolve_current_profile(&self) -> Result<Vec<ModKey>> {
+        self.resolve_enabled_mods(self.current_profile)
+    }
+
+    pub fn resolve_enabled_mods(&self, id: i32) -> Result<Vec<ModKey>> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            if !visited.insert(id) {
+                return Err(anyhow!(
+                    "Cannot resolve mod profile: inheritance cycle detected"
+                ));
+            }
+            let profile = self
+                .mod_profiles
+                .get(&id)
+                .ok_or_else(|| anyhow!("Cannot resolve mod profile: profile {id} is missing"))?;
+            chain.push(profile);
+            next = profile.parent;
+        }
+
+        let mut enabled: Vec<ModKey> = Vec::new();
+        for profile in chain.into_iter().rev() {
+            enabled.retain(|m| !profile.disabled_mods.contains(m));
+            for m in &profile.enabled_mods {
+                if !enabled.contains(m) {
+                    enabled.push(m.clone());
+                }
+            }
+        }
+        Ok(enabled)
+    }
+
+    /// Names of the profiles enabling a mod, directly or through a parent.
+    pub fn get_profiles_enabling(&self, key: &ModKey) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .mod_profiles
+            .iter()
+            .filter(|(id, _)| {
+                self.resolve_enabled_mods(**id)
+                    .is_ok_and(|enabled| enabled.contains(key))
+            })
+            .map(|(_, p)| p.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Makes every profile refer to another mod in place of the given one.
+    pub fn replace_mod(&mut self, old: &ModKey, new: &ModKey) {
+        for profile in self.mod_profiles.values_mut() {
+            for list in [&mut profile.enabled_mods, &mut profile.disabled_mods] {
+                if let Some(i) = list.iter().position(|m| m == old) {
+                    // A profile may list the copy already
+                    if list.contains(new) {
+                        list.remove(i);
+                    } else {
+                        list[i] = new.clone();
+                    }
+                    list.retain(|m| m != old);
+                }
+            }
+        }
+    }
+
+    pub fn set_current_profile_parent(&mut self, parent: Option<i32>) -> Result<()> {
+        if parent.is_some_and(|p| !self.mod_profiles.contains_key(&p)) {
+            return Err(anyhow!("Cannot set parent profile: profile does not exist"));
+        }
+        let id = self.current_profile;
+        let Some(profile) = self.get_current_profile_mut() else {
+            return Err(anyhow!("Cannot set parent profile: no profile selected"));
+        };
+        let previous_parent = std::mem::replace(&mut profile.parent, parent);
+        if let Err(e) = self.resolve_enabled_mods(id) {
+            if let Some(profile) = self.get_current_profile_mut() {
+                profile.parent = previous_parent;
+            }
+            return Err(e);
+        }
+        self.update_parent_state();
+        Ok(())
+    }
+
+    /// Stores the given enabled mods in a profile, as a difference from its parent if it has one.
+    fn set_profile_mods(&mut self, id: i32, enabled: Vec<ModKey>) {
+        let inherited = self
+            .mod_profiles
+            .get(&id)
+            .and_then(|p| p.parent)
+            .and_then(|parent| self.resolve_enabled_mods(parent).ok());
+        if let Some(profile) = self.mod_profiles.get_mut(&id) {
+            match inherited {
+                Some(inherited) => {
+                    profile.disabled_mods = inherited
+                        .iter()
+                        .filter(|m| !enabled.contains(m))
+                        .cloned()
+                        .collect();
+                    profile.enabled_mods = enabled
+                        .into_iter()
+                        .filter(|m| !inherited.contains(m))
+                        .collect();
+                }
+                None => {
+                    profile.enabled_mods = enabled;
+                    profile.disabled_mods.clear();
+                }
+            }
         }
     }
 
@@ -118,10 +262,27 @@ impl ModProfileManager {
         {
             self.current_profile_state = Some(profile_state.clone());
         }
+        self.update_parent_state();
     }
 
     pub fn delete_current_profile(&mut self) {
-        self.mod_profiles.remove(&self.current_profile);
+        // Profiles layered on top of the deleted one inherit from its parent instead
+        let id = self.current_profile;
+        let grandparent = self.mod_profiles.get(&id).and_then(|p| p.parent);
+        let children: Vec<i32> = self
+            .mod_profiles
+            .iter()
+            .filter(|(_, p)| p.parent == Some(id))
+            .map(|(i, _)| *i)
+            .collect();
+        for child in children {
+            let enabled = self.resolve_enabled_mods(child).unwrap_or_default();
+            if let Some(profile) = self.mod_profiles.get_mut(&child) {
+                profile.parent = grandparent.filter(|p| *p != child);
+            }
+            self.set_profile_mods(child, enabled);
+        }
+        self.mod_profiles.remove(&id);
         self.current_profile = 0;
         self.update_state();
     }
@@ -149,15 +310,40 @@ impl ModProfileManager {
                 .cloned()
                 .unwrap_or(Self::get_default_profile()),
         );
+        self.update_parent_state();
+    }
+
+    fn update_parent_state(&mut self) {
+        let current = self.current_profile;
+        self.parent_profile_states = self
+            .profile_states
+            .iter()
+            .filter(|s| s.id != 0 && s.id != current)
+            .cloned()
+            .collect();
+        self.parent_profile_states
+            .insert(0, Self::get_no_parent_profile());
+        let parent = self.get_current_profile().and_then(|p| p.parent);
+        self.current_parent_state = Some(
+            self.parent_profile_states
+                .iter()
+                .find(|s| Some(s.id) == parent)
+                .cloned()
+                .unwrap_or(Self::get_no_parent_profile()),
+        );
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::types::ModProfile;
+    use crate::{types::{DisableStrategy, Mod, ModKey, ModProfile}, util::test::{write_mod, TempDir}};
 
     use super::{ModProfileState, ModProfileManager};
 
+    fn keys(ids: &[u64]) -> Vec<ModKey> {
+        ids.iter().map(|id| ModKey::from(*id)).collect()
+    }
+
     fn profile_state(id: i32, name: &str) -> ModProfileState {
         ModProfileState { id, name: name.to_string() }
     }
@@ -208,7 +394,7 @@ mod test {
     fn get_current_profile() {
         let mut manager = ModProfileManager::default();
         manager.create_empty_profile("Test Profile".to_string());
-        let expected_profile = ModProfile { name: "Test Profile".to_string(), enabled_mods: Vec::new() };
+        let expected_profile = ModProfile { name: "Test Profile".to_string(), enabled_mods: Vec::new(), ..Default::default() };
         let current_profile = manager.get_current_profile().unwrap();
         assert_eq!(current_profile.name, expected_profile.name);
         assert_eq!(current_profile.enabled_mods, expected_profile.enabled_mods);
@@ -222,12 +408,12 @@ mod test {
         let current_profile = manager.get_current_profile().unwrap();
         assert!(current_profile.enabled_mods.is_empty(), "A new profile should have no enabled mods");
 
-        let enabled_mods = vec![69, 420, 727, 996, 1116];
+        let enabled_mods = keys(&[69, 420, 727, 996, 1116]);
         manager.update_current_profile(enabled_mods.clone());
         let current_profile = manager.get_current_profile().unwrap();
         assert_eq!(current_profile.enabled_mods, enabled_mods, "Updating the profile should have the given enabled mods");
 
-        let enabled_mods = vec![1, 2, 3];
+        let enabled_mods = keys(&[1, 2, 3]);
         manager.update_current_profile(enabled_mods.clone());
         let current_profile = manager.get_current_profile().unwrap();
         assert_eq!(current_profile.enabled_mods, enabled_mods, "Updating the profile should replace the previous enabled mods vector");
@@ -267,4 +453,113 @@ mod test {
         assert_eq!(profile.name, "Test Profile", "Check the new profile is named correctly");
         assert!(profile.enabled_mods.is_empty(), "Check the new profile has no enabled mods");
     }
+
+    #[test]
+    fn resolve_layered_profile() {
+        let mut manager = ModProfileManager::default();
+
+        manager.create_empty_profile("Base Profile".to_string());
+        manager.update_current_profile(keys(&[1, 2, 3]));
+        manager.create_empty_profile("Derived Profile".to_string());
+        manager.set_current_profile_parent(Some(1)).unwrap();
+        assert_eq!(manager.resolve_current_profile().unwrap(), keys(&[1, 2, 3]), "A new derived profile should inherit every mod of its parent");
+
+        manager.update_current_profile(keys(&[1, 3, 4]));
+        let profile = manager.get_current_profile().unwrap();
+        assert_eq!(profile.enabled_mods, keys(&[4]), "A derived profile should only store its own additions");
+        assert_eq!(profile.disabled_mods, keys(&[2]), "A derived profile should only store its own removals");
+
+        manager.update_selected_profile(1);
+        manager.update_current_profile(keys(&[1, 2, 3, 5]));
+        assert_eq!(manager.resolve_enabled_mods(2).unwrap(), keys(&[1, 3, 5, 4]), "Updating the base profile should update the derived profile");
+    }
+
+    #[test]
+    fn profile_inheritance_cycle() {
+        let mut manager = ModProfileManager::default();
+
+        manager.create_empty_profile("First Profile".to_string());
+        manager.create_empty_profile("Second Profile".to_string());
+        manager.set_current_profile_parent(Some(1)).unwrap();
+        manager.update_selected_profile(1);
+        assert!(manager.set_current_profile_parent(Some(2)).is_err(), "Creating an inheritance cycle should be refused");
+        assert_eq!(manager.get_current_profile().unwrap().parent, None, "A refused parent should not be kept");
+
+        manager.mod_profiles.get_mut(&1).unwrap().parent = Some(2);
+        assert!(manager.resolve_enabled_mods(1).is_err(), "Resolving a profile in a cycle should fail");
+    }
+
+    #[test]
+    fn delete_parent_profile() {
+        let mut manager = ModProfileManager::default();
+
+        manager.create_empty_profile("Base Profile".to_string());
+        manager.update_current_profile(keys(&[1, 2]));
+        manager.create_empty_profile("Middle Profile".to_string());
+        manager.set_current_profile_parent(Some(1)).unwrap();
+        manager.update_current_profile(keys(&[2, 3]));
+        manager.create_empty_profile("Top Profile".to_string());
+        manager.set_current_profile_parent(Some(2)).unwrap();
+        manager.update_current_profile(keys(&[2, 3, 4]));
+
+        manager.update_selected_profile(2);
+        manager.delete_current_profile();
+        let profile = manager.mod_profiles.get(&3).unwrap();
+        assert_eq!(profile.parent, Some(1), "Children of a deleted profile should inherit from its parent");
+        assert_eq!(manager.resolve_enabled_mods(3).unwrap(), keys(&[2, 3, 4]), "Children of a deleted profile should keep their enabled mods");
+    }
+
+    #[test]
+    fn profiles_enabling() {
+        let mut manager = ModProfileManager::default();
+        manager.create_empty_profile("Base".to_string());
+        manager.update_current_profile(keys(&[1, 2]));
+        manager.create_empty_profile("Derived".to_string());
+        manager.set_current_profile_parent(Some(1)).unwrap();
+        manager.update_current_profile(keys(&[1, 3]));
+
+        assert_eq!(manager.get_profiles_enabling(&1.into()), vec!["Base", "Derived"], "Inherited mods should count");
+        assert_eq!(manager.get_profiles_enabling(&2.into()), vec!["Base"], "Mods disabled by a child profile should not count");
+        assert!(manager.get_profiles_enabling(&4.into()).is_empty());
+    }
+
+    #[test]
+    fn local_mods_in_profile() {
+        let dir = TempDir::new("manager");
+        let mods_path = dir.join("mods");
+        write_mod(&mods_path, "first local", "");
+        write_mod(&mods_path, "second local", "");
+        let mut mod_list = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        mod_list.sort_by(|a, b| a.path.cmp(&b.path));
+        assert!(mod_list.iter().all(|m| m.metadata.id == 0), "Never uploaded mods have no workshop id");
+        assert_ne!(mod_list[0].key(), mod_list[1].key(), "Local mods should be told apart by their folder");
+
+        let mut manager = ModProfileManager::default();
+        manager.create_empty_profile("Local".to_string());
+        manager.update_current_profile(vec![mod_list[0].key()]);
+        let enabled = manager.resolve_current_profile().unwrap();
+        let enabled: Vec<&str> = mod_list.iter().filter(|m| enabled.contains(&m.key())).map(|m| m.metadata.name.as_str()).collect();
+        assert_eq!(enabled, vec!["first local"], "Enabling one local mod should not enable the other");
+        assert_eq!(manager.get_profiles_enabling(&mod_list[1].key()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn replace_mod() {
+        let mut manager = ModProfileManager::default();
+        manager.create_empty_profile("Base".to_string());
+        manager.update_current_profile(keys(&[1, 2]));
+        manager.create_empty_profile("Derived".to_string());
+        manager.set_current_profile_parent(Some(1)).unwrap();
+        manager.update_current_profile(keys(&[2]));
+
+        let copy = ModKey::Local("one (rollback 1.0)".to_string());
+        manager.replace_mod(&1.into(), &copy);
+        assert_eq!(manager.resolve_enabled_mods(1).unwrap(), vec![copy.clone(), 2.into()], "The copy should take the place of the original");
+        assert_eq!(manager.mod_profiles.get(&2).unwrap().disabled_mods, vec![copy.clone()], "Removals should refer to the copy too");
+        assert!(manager.get_profiles_enabling(&1.into()).is_empty(), "No profile should enable the original anymore");
+
+        manager.mod_profiles.get_mut(&2).unwrap().enabled_mods = vec![copy.clone(), 3.into()];
+        manager.replace_mod(&3.into(), &copy);
+        assert_eq!(manager.mod_profiles.get(&2).unwrap().enabled_mods, vec![copy], "A profile already listing the copy should not list it twice");
+    }
 }
@@ -0,0 +1,250 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use quick_xml::{events::Event, Reader, Writer};
+
+use crate::types::ModMetadata;
+
+/// Elements of `metadata.xml` owned by `ModMetadata`. Every other element is kept as is.
+const KNOWN_ELEMENTS: [&str; 7] = [
+    "name",
+    "directory",
+    "id",
+    "description",
+    "version",
+    "visibility",
+    "tag",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionPart {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// Increments a part of a dotted version, resetting the parts after it. Parts that are not
+/// numbers count as 0.
+pub fn bump_version(version: &str, part: VersionPart) -> String {
+    let mut parts: Vec<u64> = version
+        .trim()
+        .split('.')
+        .map(|p| {
+            let digits: String = p.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().unwrap_or(0)
+        })
+        .collect();
+    let index = match part {
+        VersionPart::Major => 0,
+        VersionPart::Minor => 1,
+        VersionPart::Patch => 2,
+    };
+    if parts.len() <= index {
+        parts.resize(index + 1, 0);
+    }
+    parts[index] += 1;
+    for p in &mut parts[index + 1..] {
+        *p = 0;
+    }
+    parts
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+enum Node {
+    Element(String, Vec<Event<'static>>),
+    Other(Event<'static>),
+}
+
+/// Splits a document into the events up to the root element's start, the root's children and
+/// the events from the root's end on.
+fn parse_children(xml: &str) -> Result<(Vec<Event<'static>>, Vec<Node>, Vec<Event<'static>>)> {
+    let mut reader = Reader::from_str(xml);
+    let mut prefix = Vec::new();
+    let mut children = Vec::new();
+    let mut suffix = Vec::new();
+    let mut current: Option<(String, Vec<Event<'static>>)> = None;
+    let mut depth = 0;
+    let mut closed = false;
+    loop {
+        let event = reader.read_event()?.into_owned();
+        match (&event, depth) {
+            (Event::Eof, _) => break,
+            (Event::Start(_), 0) if !closed => {
+                prefix.push(event);
+                depth = 1;
+            }
+            (Event::Empty(_), 0) if !closed => {
+                return Err(anyhow!("metadata.xml has no elements"));
+            }
+            (_, 0) if closed => suffix.push(event),
+            (_, 0) => prefix.push(event),
+            (Event::End(_), 1) => {
+                suffix.push(event);
+                depth = 0;
+                closed = true;
+            }
+            (Event::Start(e), 1) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                current = Some((name, vec![event]));
+                depth = 2;
+            }
+            (Event::Empty(e), 1) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                children.push(Node::Element(name, vec![event]));
+            }
+            (_, 1) => children.push(Node::Other(event)),
+            _ => {
+                match event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => depth -= 1,
+                    _ => {}
+                }
+                if let Some((_, events)) = &mut current {
+                    events.push(event);
+                }
+                if depth == 1 {
+                    if let Some((name, events)) = current.take() {
+                        children.push(Node::Element(name, events));
+                    }
+                }
+            }
+        }
+    }
+    if !closed {
+        return Err(anyhow!("metadata.xml is incomplete"));
+    }
+    Ok((prefix, children, suffix))
+}
+
+fn is_whitespace(event: &Event) -> bool {
+    matches!(event, Event::Text(t) if t.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Writes the fields of `metadata` into an existing `metadata.xml` document, keeping unknown
+/// elements, comments and formatting untouched.
+pub fn update_metadata(original: &str, metadata: &ModMetadata) -> Result<String> {
+    let (prefix, children, suffix) = parse_children(original)?;
+    let serialized = quick_xml::se::to_string(metadata)?;
+    let (_, new_children, _) = parse_children(&serialized)?;
+    let new_elements = |name: &str| -> Vec<Vec<Event<'static>>> {
+        new_children
+            .iter()
+            .filter_map(|n| match n {
+                Node::Element(n, events) if n == name => Some(events.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut out = prefix;
+    let mut pending: Vec<Event> = Vec::new();
+    let mut indent: Option<Event> = None;
+    let mut emitted = HashSet::new();
+    for node in children {
+        match node {
+            Node::Other(event) => {
+                if is_whitespace(&event) {
+                    indent = Some(event.clone());
+                }
+                pending.push(event);
+            }
+            Node::Element(name, _) if KNOWN_ELEMENTS.contains(&name.as_str()) => {
+                let elements = if emitted.insert(name.clone()) {
+                    new_elements(&name)
+                } else {
+                    Vec::new()
+                };
+                if elements.is_empty() {
+                    // Drop the indentation of removed elements along with them
+                    pending.retain(|e| !is_whitespace(e));
+                }
+                out.append(&mut pending);
+                for (i, events) in elements.into_iter().enumerate() {
+                    if i > 0 {
+                        out.extend(indent.clone());
+                    }
+                    out.extend(events);
+                }
+            }
+            Node::Element(_, events) => {
+                out.append(&mut pending);
+                out.extend(events);
+            }
+        }
+    }
+    // Fields the original document did not have go at the end
+    for name in KNOWN_ELEMENTS.iter().filter(|n| !emitted.contains(**n)) {
+        for events in new_elements(name) {
+            out.extend(indent.clone());
+            out.extend(events);
+        }
+    }
+    out.append(&mut pending);
+    out.extend(suffix);
+
+    let mut writer = Writer::new(Vec::new());
+    for event in out {
+        writer.write_event(event)?;
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Writes the metadata of a mod folder back to its `metadata.xml`.
+pub fn write_metadata(mod_path: &Path, metadata: &ModMetadata) -> Result<()> {
+    let path = mod_path.join("metadata.xml");
+    let original = fs::read_to_string(&path)?;
+    fs::write(path, update_metadata(&original, metadata)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::{ModMetadata, ModTag, ModTagId};
+
+    use super::{bump_version, update_metadata, VersionPart};
+
+    const ORIGINAL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+	<!-- Keep this comment -->
+	<name>Old Name</name>
+	<directory>old name</directory>
+	<id>1234</id>
+	<description>Fixes &amp; tweaks</description>
+	<version>1.0</version>
+	<visibility>Public</visibility>
+	<tag id="Lua"/>
+	<tag id="Items"/>
+	<customfield note="unknown">keep me</customfield>
+</metadata>
+"#;
+
+    #[test]
+    fn round_trip_metadata() {
+        let mut metadata: ModMetadata = quick_xml::de::from_str(ORIGINAL).unwrap();
+        assert_eq!(update_metadata(ORIGINAL, &metadata).unwrap(), ORIGINAL, "Unchanged metadata should be written back as is");
+
+        metadata.name = "New <Name>".to_string();
+        metadata.tags = Some(vec![ModTag { id: ModTagId::ActiveItems }]);
+        let updated = update_metadata(ORIGINAL, &metadata).unwrap();
+        assert!(updated.contains("<!-- Keep this comment -->"), "Comments should be kept");
+        assert!(updated.contains(r#"<customfield note="unknown">keep me</customfield>"#), "Unknown elements should be kept");
+        assert!(updated.contains("\t<tag id=\"Active Items\"/>\n\t<customfield"), "Removed tags should not leave blank lines");
+
+        let parsed: ModMetadata = quick_xml::de::from_str(&updated).unwrap();
+        assert_eq!(parsed.name, "New <Name>");
+        assert_eq!(parsed.id, 1234);
+        assert_eq!(parsed.tags.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn bump_versions() {
+        assert_eq!(bump_version("1.0", VersionPart::Patch), "1.0.1");
+        assert_eq!(bump_version("1.2.3", VersionPart::Minor), "1.3.0");
+        assert_eq!(bump_version("1.2.3", VersionPart::Major), "2.0.0");
+        assert_eq!(bump_version("v2", VersionPart::Major), "1", "Versions that are not numbers start over");
+        assert_eq!(bump_version("1.4b", VersionPart::Minor), "1.5");
+    }
+}
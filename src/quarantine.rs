@@ -0,0 +1,114 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{log::LogReport, types::ModKey, util::get_config_dir};
+
+/// Mods disabled because they flooded the game log with errors.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Quarantine {
+    pub entries: Vec<QuarantineEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub mod_id: ModKey,
+    pub error_count: usize,
+    /// Latest error the mod threw before being quarantined.
+    pub error: String,
+}
+
+impl Quarantine {
+    pub fn load() -> Result<Self> {
+        if let Some(path) = get_config_dir() {
+            let quarantine_path = path.join("quarantine.json");
+            let quarantine_contents = fs::read_to_string(quarantine_path)?;
+            let quarantine = serde_json::from_str(&quarantine_contents)?;
+            Ok(quarantine)
+        } else {
+            Err(anyhow!("Cannot load quarantine: directory somehow missing"))
+        }
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = get_config_dir() {
+            if !path.exists() {
+                fs::create_dir(&path)?;
+            }
+            let quarantine_path = path.join("quarantine.json");
+            let quarantine_contents = serde_json::to_string_pretty(self)?;
+            fs::write(quarantine_path, quarantine_contents)?;
+            Ok(())
+        } else {
+            Err(anyhow!("Cannot save quarantine: directory somehow missing"))
+        }
+    }
+
+    pub fn contains(&self, key: &ModKey) -> bool {
+        self.entries.iter().any(|e| e.mod_id == *key)
+    }
+
+    /// Returns entries for every mod whose error count exceeds the threshold and is not
+    /// quarantined yet.
+    pub fn propose(&self, report: &LogReport, threshold: usize) -> Vec<QuarantineEntry> {
+        report
+            .mods
+            .iter()
+            .filter(|e| e.count > threshold && !self.contains(&e.mod_id))
+            .map(|e| QuarantineEntry {
+                mod_id: e.mod_id.clone(),
+                error_count: e.count,
+                error: e.latest.last().cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub fn add(&mut self, entry: QuarantineEntry) {
+        if !self.contains(&entry.mod_id) {
+            self.entries.push(entry);
+        }
+    }
+
+    pub fn release(&mut self, key: &ModKey) {
+        self.entries.retain(|e| e.mod_id != *key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        log::{LogReport, ModErrors},
+        types::ModKey,
+    };
+
+    use super::{Quarantine, QuarantineEntry};
+
+    fn errors(mod_id: u64, count: usize) -> ModErrors {
+        ModErrors { mod_id: mod_id.into(), count, latest: vec![format!("error {count}")] }
+    }
+
+    #[test]
+    fn propose_quarantine() {
+        let mut quarantine = Quarantine::default();
+        let report = LogReport { mods: vec![errors(1, 50), errors(2, 3), errors(3, 20)], unattributed: 0 };
+
+        let proposed = quarantine.propose(&report, 10);
+        let ids: Vec<ModKey> = proposed.iter().map(|e| e.mod_id.clone()).collect();
+        assert_eq!(ids, vec![1.into(), 3.into()], "Only mods above the threshold should be proposed");
+        assert_eq!(proposed[0].error, "error 50", "The latest error should be recorded");
+
+        quarantine.add(proposed[0].clone());
+        let ids: Vec<ModKey> = quarantine.propose(&report, 10).iter().map(|e| e.mod_id.clone()).collect();
+        assert_eq!(ids, vec![3.into()], "Quarantined mods should not be proposed again");
+
+        quarantine.release(&1.into());
+        assert!(quarantine.entries.is_empty(), "Released mods should leave the quarantine");
+        quarantine.add(QuarantineEntry { mod_id: 2.into(), error_count: 3, error: String::new() });
+        assert!(quarantine.contains(&2.into()));
+    }
+}
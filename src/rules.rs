@@ -0,0 +1,209 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{types::ModKey, util::get_config_dir};
+
+/// User-declared relations between mods, since Isaac metadata has no dependency field.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ModRules {
+    pub rules: Vec<ModRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModRule {
+    pub mod_id: ModKey,
+    pub kind: RuleKind,
+    pub other_id: ModKey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RuleKind {
+    #[default]
+    Requires,
+    ConflictsWith,
+}
+
+impl RuleKind {
+    pub const ALL: [Self; 2] = [Self::Requires, Self::ConflictsWith];
+}
+
+impl std::fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Requires => "requires",
+                Self::ConflictsWith => "conflicts with",
+            }
+        )
+    }
+}
+
+/// Rule violations found in a set of enabled mods.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleReport {
+    /// Enabled mods paired with a dependency that is not enabled.
+    pub missing: Vec<(ModKey, ModKey)>,
+    /// Pairs of enabled mods that conflict with each other.
+    pub conflicts: Vec<(ModKey, ModKey)>,
+}
+
+impl RuleReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.conflicts.is_empty()
+    }
+}
+
+impl ModRules {
+    pub fn load() -> Result<Self> {
+        if let Some(path) = get_config_dir() {
+            let rules_path = path.join("rules.json");
+            let rules_contents = fs::read_to_string(rules_path)?;
+            let rules = serde_json::from_str(&rules_contents)?;
+            Ok(rules)
+        } else {
+            Err(anyhow!("Cannot load mod rules: directory somehow missing"))
+        }
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = get_config_dir() {
+            if !path.exists() {
+                fs::create_dir(&path)?;
+            }
+            let rules_path = path.join("rules.json");
+            let rules_contents = serde_json::to_string_pretty(self)?;
+            fs::write(rules_path, rules_contents)?;
+            Ok(())
+        } else {
+            Err(anyhow!("Cannot save mod rules: directory somehow missing"))
+        }
+    }
+
+    pub fn add(&mut self, rule: ModRule) {
+        if rule.mod_id != rule.other_id && !self.rules.contains(&rule) {
+            self.rules.push(rule);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.rules.len() {
+            self.rules.remove(index);
+        }
+    }
+
+    /// Makes every rule refer to another mod in place of the given one.
+    pub fn replace_mod(&mut self, old: &ModKey, new: &ModKey) {
+        for rule in &mut self.rules {
+            for key in [&mut rule.mod_id, &mut rule.other_id] {
+                if key == old {
+                    *key = new.clone();
+                }
+            }
+        }
+    }
+
+    pub fn check(&self, enabled: &[ModKey]) -> RuleReport {
+        let mut report = RuleReport::default();
+        for rule in self.rules.iter().filter(|r| enabled.contains(&r.mod_id)) {
+            match rule.kind {
+                RuleKind::Requires => {
+                    if !enabled.contains(&rule.other_id) {
+                        report
+                            .missing
+                            .push((rule.mod_id.clone(), rule.other_id.clone()));
+                    }
+                }
+                RuleKind::ConflictsWith => {
+                    if enabled.contains(&rule.other_id) {
+                        report
+                            .conflicts
+                            .push((rule.mod_id.clone(), rule.other_id.clone()));
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Returns every mod that must be enabled on top of the given ones to satisfy the
+    /// dependencies, following dependencies of dependencies.
+    pub fn missing_dependencies(&self, enabled: &[ModKey]) -> Vec<ModKey> {
+        let mut enabled = enabled.to_vec();
+        let mut missing = Vec::new();
+        loop {
+            let report = self.check(&enabled);
+            let new: Vec<ModKey> = report
+                .missing
+                .into_iter()
+                .map(|(_, dependency)| dependency)
+                .filter(|d| !enabled.contains(d))
+                .collect();
+            if new.is_empty() {
+                return missing;
+            }
+            for dependency in new {
+                if !enabled.contains(&dependency) {
+                    enabled.push(dependency.clone());
+                    missing.push(dependency);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::ModKey;
+
+    use super::{ModRule, ModRules, RuleKind};
+
+    fn rule(mod_id: u64, kind: RuleKind, other_id: u64) -> ModRule {
+        ModRule { mod_id: mod_id.into(), kind, other_id: other_id.into() }
+    }
+
+    fn keys(ids: &[u64]) -> Vec<ModKey> {
+        ids.iter().map(|id| ModKey::from(*id)).collect()
+    }
+
+    #[test]
+    fn check_rules() {
+        let mut rules = ModRules::default();
+        rules.add(rule(1, RuleKind::Requires, 2));
+        rules.add(rule(1, RuleKind::ConflictsWith, 3));
+
+        let report = rules.check(&keys(&[1, 3]));
+        assert_eq!(report.missing, vec![(1.into(), 2.into())], "Mod 1 requires mod 2, which is disabled");
+        assert_eq!(report.conflicts, vec![(1.into(), 3.into())], "Mod 1 conflicts with mod 3, which is enabled");
+
+        let report = rules.check(&keys(&[2, 3]));
+        assert!(report.is_empty(), "Rules of a disabled mod should not be checked");
+    }
+
+    #[test]
+    fn missing_dependencies() {
+        let mut rules = ModRules::default();
+        rules.add(rule(1, RuleKind::Requires, 2));
+        rules.add(rule(2, RuleKind::Requires, 3));
+        rules.add(rule(4, RuleKind::Requires, 3));
+
+        assert_eq!(rules.missing_dependencies(&keys(&[1])), keys(&[2, 3]), "Dependencies of dependencies should be enabled too");
+        assert_eq!(rules.missing_dependencies(&keys(&[1, 2, 3])), Vec::<ModKey>::new(), "There should be nothing to enable");
+    }
+
+    #[test]
+    fn add_rule() {
+        let mut rules = ModRules::default();
+        rules.add(rule(1, RuleKind::Requires, 2));
+        rules.add(rule(1, RuleKind::Requires, 2));
+        rules.add(rule(1, RuleKind::Requires, 1));
+        assert_eq!(rules.rules.len(), 1, "Duplicate and self-referencing rules should be ignored");
+    }
+}
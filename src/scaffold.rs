@@ -0,0 +1,154 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use quick_xml::escape::escape;
+
+use crate::types::{Mod, ModTagId};
+
+/// Visibilities the workshop uploader accepts.
+pub const VISIBILITIES: [&str; 3] = ["Public", "FriendsOnly", "Private"];
+
+/// What the new mod wizard collected.
+#[derive(Debug, Clone)]
+pub struct NewMod {
+    pub name: String,
+    pub directory: String,
+    pub description: String,
+    pub version: String,
+    pub visibility: &'static str,
+    pub tags: Vec<ModTagId>,
+    pub with_content: bool,
+    pub with_resources: bool,
+}
+
+impl Default for NewMod {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            directory: String::new(),
+            description: String::new(),
+            version: "1.0".to_string(),
+            visibility: VISIBILITIES[0],
+            tags: vec![ModTagId::Lua],
+            with_content: false,
+            with_resources: false,
+        }
+    }
+}
+
+/// Folder name derived from a mod name, used until one is typed in.
+pub fn directory_from_name(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+impl NewMod {
+    pub fn metadata_xml(&self) -> String {
+        let tags: String = self
+            .tags
+            .iter()
+            .map(|t| format!("\t<tag id=\"{}\"/>\n", escape(&t.to_string())))
+            .collect();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<metadata>
+\t<name>{}</name>
+\t<directory>{}</directory>
+\t<description>{}</description>
+\t<version>{}</version>
+\t<visibility>{}</visibility>
+{tags}</metadata>
+",
+            escape(self.name.trim()),
+            escape(self.directory.trim()),
+            escape(self.description.trim()),
+            escape(self.version.trim()),
+            self.visibility,
+        )
+    }
+
+    pub fn main_lua(&self) -> String {
+        let name = self.name.trim().replace('\\', "\\\\").replace('"', "\\\"");
+        format!(
+            "local mod = RegisterMod(\"{name}\", 1)
+
+-- Register callbacks on the mod, for example:
+-- mod:AddCallback(ModCallbacks.MC_POST_PLAYER_INIT, function(_, player)
+-- end)
+"
+        )
+    }
+
+    /// Creates the mod folder in the mods folder and returns its path.
+    pub fn create(&self, mods_path: &Path, mod_list: &[Mod]) -> Result<PathBuf> {
+        if mods_path.as_os_str().is_empty() || mods_path.is_relative() {
+            return Err(anyhow!("Invalid mod path set!"));
+        }
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("The mod needs a name"));
+        }
+        let directory = self.directory.trim();
+        if directory.is_empty() || directory.contains(['/', '\\']) || directory.starts_with('.') {
+            return Err(anyhow!("Invalid mod directory name: {directory}"));
+        }
+        let target = mods_path.join(directory);
+        let clashes = mod_list
+            .iter()
+            .any(|m| m.metadata.directory.eq_ignore_ascii_case(directory));
+        if clashes || target.exists() {
+            return Err(anyhow!("A mod already uses the directory {directory}"));
+        }
+
+        fs::create_dir_all(&target)?;
+        fs::write(target.join("metadata.xml"), self.metadata_xml())?;
+        fs::write(target.join("main.lua"), self.main_lua())?;
+        if self.with_content {
+            fs::create_dir_all(target.join("content"))?;
+            fs::write(
+                target.join("content").join("items.xml"),
+                "<items gfxroot=\"gfx/items/\" version=\"1\">\n</items>\n",
+            )?;
+        }
+        if self.with_resources {
+            for dir in ["gfx/items/collectibles", "sfx", "music"] {
+                fs::create_dir_all(target.join("resources").join(dir))?;
+            }
+        }
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{types::{Mod, ModTagId}, util::test::TempDir};
+
+    use super::NewMod;
+
+    #[test]
+    fn create_mod() {
+        let dir = TempDir::new("scaffold");
+        let new_mod = NewMod {
+            name: "Tom & \"Jerry\"".to_string(),
+            directory: "tom_and_jerry".to_string(),
+            tags: vec![ModTagId::Lua, ModTagId::ActiveItems],
+            with_content: true,
+            ..Default::default()
+        };
+        let path = new_mod.create(&dir, &[]).unwrap();
+        let m = Mod::from_path(path).unwrap();
+        assert_eq!(m.metadata.name, "Tom & \"Jerry\"", "The name should survive XML escaping");
+        assert_eq!(m.metadata.id, 0, "New mods have no workshop id yet");
+        assert!(m.has_tag(ModTagId::ActiveItems), "Tags should use the names the game expects");
+        assert!(m.path.join("content/items.xml").exists());
+        assert!(fs::read_to_string(m.path.join("main.lua")).unwrap().contains("RegisterMod(\"Tom & \\\"Jerry\\\"\", 1)"));
+        assert!(new_mod.create(&dir, &[]).is_err(), "Existing folders should not be overwritten");
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::types::Mod;
+
+/// Steam app id of The Binding of Isaac: Rebirth.
+pub const ISAAC_APP_ID: &str = "250900";
+
+/// A value of Valve's KeyValues text format, used by `.vdf` and `.acf` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Vdf {
+    Value(String),
+    Object(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let tokens = tokenize(contents)?;
+        let mut tokens = tokens.into_iter();
+        let object = parse_object(&mut tokens, false)?;
+        Ok(Self::Object(object))
+    }
+
+    /// Looks a key up, ignoring case like Steam does.
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Self::Value(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Value(v) => Some(v),
+            Self::Object(_) => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, Vdf)] {
+        match self {
+            Self::Object(entries) => entries,
+            Self::Value(_) => &[],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+fn tokenize(contents: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(c) => value.push(c),
+                            None => return Err(anyhow!("Unterminated string in VDF file")),
+                        },
+                        Some(c) => value.push(c),
+                        None => return Err(anyhow!("Unterminated string in VDF file")),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                // Unquoted tokens are allowed by the format, even if Steam never writes them
+                let mut value = String::from(c);
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || *c == '{' || *c == '}' || *c == '"' {
+                        break;
+                    }
+                    value.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Str(value));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_object(
+    tokens: &mut impl Iterator<Item = Token>,
+    nested: bool,
+) -> Result<Vec<(String, Vdf)>> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Str(key)) => key,
+            Some(Token::Close) if nested => return Ok(entries),
+            None if !nested => return Ok(entries),
+            _ => return Err(anyhow!("Malformed VDF file")),
+        };
+        let value = match tokens.next() {
+            Some(Token::Str(value)) => Vdf::Value(value),
+            Some(Token::Open) => Vdf::Object(parse_object(tokens, true)?),
+            _ => return Err(anyhow!("Malformed VDF file: missing value for {key}")),
+        };
+        entries.push((key, value));
+    }
+}
+
+/// An installation of the game found in a Steam library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameInstall {
+    pub library: PathBuf,
+    pub game_path: PathBuf,
+    pub mods_path: PathBuf,
+}
+
+/// Default locations of Steam, for native, Flatpak and Windows installs.
+pub fn default_steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Some(dirs) = directories::BaseDirs::new() {
+        let home = dirs.home_dir();
+        roots.push(home.join(".steam/steam"));
+        roots.push(home.join(".steam/root"));
+        roots.push(home.join(".local/share/Steam"));
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/data/Steam"));
+        roots.push(home.join("Library/Application Support/Steam"));
+    }
+    roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+    roots.push(PathBuf::from(r"C:\Program Files\Steam"));
+    roots
+}
+
+/// Returns the libraries listed in a Steam root's `libraryfolders.vdf`, including the root.
+pub fn find_libraries(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    let vdf_path = root.join("steamapps").join("libraryfolders.vdf");
+    let Some(vdf) = fs::read_to_string(vdf_path)
+        .ok()
+        .and_then(|c| Vdf::parse(&c).ok())
+    else {
+        return libraries;
+    };
+    let folders = vdf.get("libraryfolders").map_or(&[][..], Vdf::entries);
+    for (_, folder) in folders {
+        // Newer files hold an object per library, older ones only its path
+        let path = folder.get("path").and_then(Vdf::as_str).or(folder.as_str());
+        if let Some(path) = path {
+            libraries.push(PathBuf::from(path));
+        }
+    }
+    libraries
+}
+
+/// Finds the game in a Steam library through its app manifest.
+pub fn find_game(library: &Path) -> Option<GameInstall> {
+    let steamapps = library.join("steamapps");
+    let manifest_path = steamapps.join(format!("appmanifest_{ISAAC_APP_ID}.acf"));
+    let manifest = Vdf::parse(&fs::read_to_string(manifest_path).ok()?).ok()?;
+    let install_dir = manifest.get("AppState")?.get("installdir")?.as_str()?;
+    let game_path = steamapps.join("common").join(install_dir);
+    Some(GameInstall {
+        library: library.to_path_buf(),
+        mods_path: game_path.join("mods"),
+        game_path,
+    })
+}
+
+/// Finds every installation of the game in the default and the given Steam roots.
+pub fn detect_installs(extra_roots: &[PathBuf]) -> Vec<GameInstall> {
+    let mut installs: Vec<GameInstall> = Vec::new();
+    let mut seen = Vec::new();
+    for root in extra_roots.iter().cloned().chain(default_steam_roots()) {
+        for library in find_libraries(&root) {
+            let Ok(canonical) = library.canonicalize() else {
+                continue;
+            };
+            if seen.contains(&canonical) {
+                continue;
+            }
+            seen.push(canonical);
+            if let Some(install) = find_game(&library) {
+                installs.push(install);
+            }
+        }
+    }
+    installs
+}
+
+/// A workshop item known to Steam.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkshopItem {
+    pub id: u64,
+    pub subscribed: bool,
+    /// Install size in bytes.
+    pub size: u64,
+    /// Unix timestamp of the last update of the item.
+    pub time_updated: u64,
+}
+
+/// Where a mod folder comes from according to Steam.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkshopStatus {
+    Subscribed,
+    /// Downloaded from the workshop, but no longer subscribed to. Steam may delete it anytime.
+    Orphaned,
+    /// Never downloaded from the workshop.
+    LocalOnly,
+}
+
+/// Workshop items of the game, read from Steam's `appworkshop_250900.acf`.
+#[derive(Debug, Clone, Default)]
+pub struct Workshop {
+    pub items: HashMap<u64, WorkshopItem>,
+}
+
+impl Workshop {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let vdf = Vdf::parse(contents)?;
+        let root = vdf
+            .get("AppWorkshop")
+            .ok_or_else(|| anyhow!("Malformed workshop manifest: missing AppWorkshop"))?;
+        let number = |item: &Vdf, key: &str| {
+            item.get(key)
+                .and_then(Vdf::as_str)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0)
+        };
+
+        let mut items: HashMap<u64, WorkshopItem> = HashMap::new();
+        let installed = root
+            .get("WorkshopItemsInstalled")
+            .map_or(&[][..], Vdf::entries);
+        for (id, item) in installed {
+            let Ok(id) = id.parse() else { continue };
+            items.insert(
+                id,
+                WorkshopItem {
+                    id,
+                    subscribed: true,
+                    size: number(item, "size"),
+                    time_updated: number(item, "timeupdated"),
+                },
+            );
+        }
+        // Details hold who subscribed to the item, 0 once nobody is
+        let details = root
+            .get("WorkshopItemDetails")
+            .map_or(&[][..], Vdf::entries);
+        for (id, detail) in details {
+            let Ok(id) = id.parse() else { continue };
+            let item = items.entry(id).or_insert(WorkshopItem {
+                id,
+                subscribed: true,
+                size: 0,
+                time_updated: number(detail, "timeupdated"),
+            });
+            if detail.get("subscribedby").is_some() {
+                item.subscribed = number(detail, "subscribedby") != 0;
+            }
+        }
+        Ok(Self { items })
+    }
+
+    /// Reads the workshop manifest of the Steam library a mods folder is in.
+    pub fn from_mods_path(mods_path: &Path) -> Result<Self> {
+        // The mods folder is in steamapps/common/<game>/mods
+        let steamapps = mods_path
+            .ancestors()
+            .nth(3)
+            .ok_or_else(|| anyhow!("Mods folder is not in a Steam library"))?;
+        let manifest_path = steamapps
+            .join("workshop")
+            .join(format!("appworkshop_{ISAAC_APP_ID}.acf"));
+        Self::parse(&fs::read_to_string(manifest_path)?)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&WorkshopItem> {
+        self.items.get(&id).filter(|i| i.subscribed)
+    }
+
+    pub fn status(&self, m: &Mod) -> WorkshopStatus {
+        if self.get(m.metadata.id).is_some() {
+            return WorkshopStatus::Subscribed;
+        }
+        // The game names the folders of workshop mods after their id
+        let from_workshop = self.items.contains_key(&m.metadata.id)
+            || m.path.file_name().is_some_and(|f| {
+                f.to_string_lossy()
+                    .ends_with(&format!("_{}", m.metadata.id))
+            });
+        if from_workshop {
+            WorkshopStatus::Orphaned
+        } else {
+            WorkshopStatus::LocalOnly
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Vdf, Workshop};
+
+    #[test]
+    fn parse_library_folders() {
+        let vdf = Vdf::parse(r#"
+"libraryfolders"
+{
+    // Comments are allowed
+    "0"
+    {
+        "path"		"/home/user/.local/share/Steam"
+        "apps"
+        {
+            "250900"		"1234"
+        }
+    }
+    "1"
+    {
+        "path"		"D:\\SteamLibrary"
+    }
+}
+"#).unwrap();
+        let folders = vdf.get("LibraryFolders").unwrap().entries();
+        assert_eq!(folders.len(), 2, "Keys should be looked up ignoring case");
+        assert_eq!(folders[0].1.get("path").and_then(Vdf::as_str), Some("/home/user/.local/share/Steam"));
+        assert_eq!(folders[1].1.get("path").and_then(Vdf::as_str), Some(r"D:\SteamLibrary"), "Escaped backslashes should be unescaped");
+        assert!(folders[0].1.get("apps").unwrap().get("250900").is_some());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert!(Vdf::parse(r#""AppState" { "installdir" "#).is_err());
+        assert!(Vdf::parse(r#""AppState" { "installdir" "Isaac" "#).is_err(), "Unclosed objects should be refused");
+    }
+
+    #[test]
+    fn parse_workshop() {
+        let workshop = Workshop::parse(r#"
+"AppWorkshop"
+{
+    "appid"		"250900"
+    "WorkshopItemsInstalled"
+    {
+        "1001"
+        {
+            "size"		"52428800"
+            "timeupdated"		"1700000000"
+            "manifest"		"123"
+        }
+        "1002"
+        {
+            "size"		"1024"
+            "timeupdated"		"1600000000"
+        }
+    }
+    "WorkshopItemDetails"
+    {
+        "1001"
+        {
+            "timeupdated"		"1700000000"
+            "subscribedby"		"76561198000000000"
+        }
+        "1002"
+        {
+            "subscribedby"		"0"
+        }
+    }
+}
+"#).unwrap();
+        let item = workshop.get(1001).unwrap();
+        assert_eq!((item.size, item.time_updated), (52428800, 1700000000));
+        assert!(workshop.items.contains_key(&1002));
+        assert!(workshop.get(1002).is_none(), "Items nobody subscribes to should not count as subscribed");
+        assert!(workshop.get(1003).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::{Mod, ModKey},
+    util::{copy_dir, get_config_dir},
+};
+
+/// An uninstalled mod, kept until purged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub mod_id: ModKey,
+    pub name: String,
+    pub version: String,
+    /// Where the mod folder was before being uninstalled.
+    pub original_path: PathBuf,
+    /// Name of the folder in the trash.
+    pub folder: String,
+    /// Unix timestamp of the uninstall.
+    pub time: u64,
+    /// Profiles that enabled the mod when it was uninstalled.
+    pub profiles: Vec<String>,
+}
+
+/// Mods uninstalled through the manager, moved to its own folder rather than deleted.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Trash {
+    pub entries: Vec<TrashEntry>,
+}
+
+impl Trash {
+    fn get_trash_dir() -> Option<PathBuf> {
+        Some(get_config_dir()?.join("trash"))
+    }
+
+    pub fn load() -> Result<Self> {
+        if let Some(path) = Self::get_trash_dir() {
+            let trash_path = path.join("trash.json");
+            let trash_contents = fs::read_to_string(trash_path)?;
+            let trash = serde_json::from_str(&trash_contents)?;
+            Ok(trash)
+        } else {
+            Err(anyhow!("Cannot load trash: directory somehow missing"))
+        }
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = Self::get_trash_dir() {
+            if !path.exists() {
+                fs::create_dir_all(&path)?;
+            }
+            let trash_path = path.join("trash.json");
+            let trash_contents = serde_json::to_string_pretty(self)?;
+            fs::write(trash_path, trash_contents)?;
+            Ok(())
+        } else {
+            Err(anyhow!("Cannot save trash: directory somehow missing"))
+        }
+    }
+
+    /// Moves a mod folder to the trash.
+    pub fn uninstall(&mut self, m: &Mod, profiles: Vec<String>) -> Result<()> {
+        let dir = Self::get_trash_dir()
+            .ok_or_else(|| anyhow!("Cannot uninstall mod: directory somehow missing"))?;
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        // Local mods have no workshop id, and a mod can be uninstalled twice in a second
+        let name = m
+            .path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let folder = (0..)
+            .map(|n| match n {
+                0 => format!("{name}_{time}"),
+                n => format!("{name}_{time}_{n}"),
+            })
+            .find(|folder| !dir.join(folder).exists())
+            .unwrap_or_default();
+        fs::create_dir_all(&dir)?;
+        move_dir(&m.path, &dir.join(&folder))?;
+        self.entries.push(TrashEntry {
+            mod_id: m.key(),
+            name: m.metadata.name.clone(),
+            version: m.metadata.version.clone(),
+            original_path: m.path.clone(),
+            folder,
+            time,
+            profiles,
+        });
+        Ok(())
+    }
+
+    /// Moves a mod back to where it was uninstalled from.
+    pub fn restore(&mut self, index: usize) -> Result<PathBuf> {
+        let dir = Self::get_trash_dir()
+            .ok_or_else(|| anyhow!("Cannot restore mod: directory somehow missing"))?;
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| anyhow!("Cannot restore mod: not in the trash"))?;
+        if entry.original_path.exists() {
+            return Err(anyhow!(
+                "Cannot restore mod: {} already exists",
+                entry.original_path.display()
+            ));
+        }
+        move_dir(&dir.join(&entry.folder), &entry.original_path)?;
+        Ok(self.entries.remove(index).original_path)
+    }
+
+    /// Deletes a trashed mod for good.
+    pub fn purge(&mut self, index: usize) -> Result<()> {
+        let dir = Self::get_trash_dir()
+            .ok_or_else(|| anyhow!("Cannot purge mod: directory somehow missing"))?;
+        if let Some(entry) = self.entries.get(index) {
+            let path = dir.join(&entry.folder);
+            if path.exists() {
+                fs::remove_dir_all(path)?;
+            }
+            self.entries.remove(index);
+        }
+        Ok(())
+    }
+}
+
+/// Moves a folder, copying it when it has to cross file systems.
+pub fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        return Err(anyhow!(
+            "Cannot move folder: {} already exists",
+            to.display()
+        ));
+    }
+    if fs::rename(from, to).is_err() {
+        if let Err(e) = copy_dir(from, to) {
+            let _ = fs::remove_dir_all(to);
+            return Err(e.into());
+        }
+        fs::remove_dir_all(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::util::test::TempDir;
+
+    use super::move_dir;
+
+    #[test]
+    fn move_folder() {
+        let dir = TempDir::new("trash");
+        fs::create_dir_all(dir.join("mod/scripts")).unwrap();
+        fs::write(dir.join("mod/scripts/main.lua"), "").unwrap();
+        move_dir(&dir.join("mod"), &dir.join("trashed")).unwrap();
+        assert!(!dir.join("mod").exists(), "The original folder should be gone");
+        assert!(dir.join("trashed/scripts/main.lua").exists(), "Nested files should be moved");
+
+        fs::create_dir_all(dir.join("other")).unwrap();
+        assert!(move_dir(&dir.join("other"), &dir.join("trashed")).is_err(), "Folders should not be merged");
+        assert!(dir.join("other").exists());
+    }
+}
@@ -1,13 +1,27 @@
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
-use crate::util::{create_empty_file, get_config_dir};
+use crate::{
+    content::ContentSummary,
+    export::default_export_ignore,
+    util::{create_empty_file, find_disable_markers, get_config_dir, list_files},
+};
 
 #[derive(Debug)]
 pub struct Mod {
     pub metadata: ModMetadata,
     pub path: PathBuf,
+    /// Files under `resources/` and `content/`, relative to the mod folder, lowercase and with
+    /// `/` separators.
+    pub resource_files: Vec<String>,
+    pub content: ContentSummary,
+    /// Mods folder of the game, which parked mods are moved back to when enabled.
+    pub mods_path: PathBuf,
+    pub strategy: DisableStrategy,
 }
 
 impl Mod {
@@ -15,45 +29,183 @@ impl Mod {
         let metadata_path = path.join("metadata.xml");
         let metadata_contents = fs::read_to_string(metadata_path)?;
         let metadata = quick_xml::de::from_str(&metadata_contents)?;
-        Ok(Self { metadata, path })
+        let files = Self::index_files(&path);
+        let content = ContentSummary::from_files(&path, &files);
+        let resource_files = files
+            .into_iter()
+            .filter(|f| f.starts_with("resources/") || f.starts_with("content/"))
+            .collect();
+        let mods_path = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(Self {
+            metadata,
+            path,
+            resource_files,
+            content,
+            mods_path,
+            strategy: DisableStrategy::default(),
+        })
+    }
+
+    /// Loads every mod of a mods folder, along with the mods parked next to it. Folders that are
+    /// not valid mods are skipped.
+    pub fn load_all(mods_path: &Path, strategy: DisableStrategy) -> anyhow::Result<Vec<Self>> {
+        if mods_path.as_os_str().is_empty() || mods_path.is_relative() {
+            return Err(anyhow!("Invalid mod path set!"));
+        }
+        let parked = fs::read_dir(parking_path(mods_path)).into_iter().flatten();
+        let mut mods = Vec::new();
+        for entry in fs::read_dir(mods_path)?.chain(parked).flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                match Self::from_path(path) {
+                    Ok(mut m) => {
+                        m.mods_path = mods_path.to_path_buf();
+                        m.strategy = strategy;
+                        mods.push(m)
+                    }
+                    Err(e) => eprintln!("Error loading mod: {e}"),
+                }
+            }
+        }
+        Ok(mods)
+    }
+
+    fn index_files(path: &Path) -> Vec<String> {
+        list_files(path)
+            .into_iter()
+            .filter_map(|file| {
+                let relative = file.strip_prefix(path).ok()?;
+                let components: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+                    .collect();
+                Some(components.join("/"))
+            })
+            .collect()
     }
 
     pub fn disable_path(&self) -> PathBuf {
         self.path.join("disable.it")
     }
 
+    /// Whether the mod was moved out of the mods folder to disable it.
+    pub fn parked(&self) -> bool {
+        self.path.parent() != Some(self.mods_path.as_path())
+    }
+
     pub fn enabled(&self) -> bool {
-        !self.disable_path().exists()
+        !self.parked() && find_disable_markers(&self.path).is_empty()
     }
 
+    /// Enables or disables the mod with its strategy. Mods disabled with the other strategy are
+    /// switched over, and at most one marker spelled `disable.it` is left.
     pub fn set_enabled(&mut self, enabled: bool) -> io::Result<()> {
-        match enabled {
-            true => fs::remove_file(self.disable_path()),
-            false => create_empty_file(self.disable_path()),
+        let park = !enabled && self.strategy == DisableStrategy::ParkingFolder;
+        let target = match park {
+            true => parking_path(&self.mods_path),
+            false => self.mods_path.clone(),
+        };
+        if self.path.parent() != Some(target.as_path()) {
+            // Marked first, so that a failed move cannot leave the mod enabled
+            if !enabled && find_disable_markers(&self.path).is_empty() {
+                create_empty_file(self.disable_path())?;
+            }
+            self.move_to(&target)?;
+        }
+        for marker in find_disable_markers(&self.path) {
+            if enabled || park || marker != self.disable_path() {
+                fs::remove_file(marker)?;
+            }
+        }
+        if !enabled && !park && !self.disable_path().exists() {
+            create_empty_file(self.disable_path())?;
         }
+        Ok(())
+    }
+
+    fn move_to(&mut self, dir: &Path) -> io::Result<()> {
+        let target = dir.join(self.path.file_name().unwrap_or_default());
+        if target.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", target.display()),
+            ));
+        }
+        fs::create_dir_all(dir)?;
+        fs::rename(&self.path, &target)?;
+        self.path = target;
+        Ok(())
+    }
+
+    /// Key identifying the mod in profiles, pins, rules and everything else that refers to it.
+    pub fn key(&self) -> ModKey {
+        match self.metadata.id {
+            0 => ModKey::Local(
+                self.path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            id => ModKey::Workshop(id),
+        }
+    }
+
+    pub fn has_tag(&self, tag: ModTagId) -> bool {
+        self.metadata.tags.iter().flatten().any(|t| t.id == tag)
+    }
+}
+
+/// Identifies a mod: its workshop id, or the name of its folder for mods that were never
+/// uploaded. Serialized as a bare number or string, so files written with workshop ids only
+/// still load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ModKey {
+    Workshop(u64),
+    Local(String),
+}
+
+impl From<u64> for ModKey {
+    fn from(id: u64) -> Self {
+        Self::Workshop(id)
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl std::fmt::Display for ModKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Workshop(id) => write!(f, "{id}"),
+            Self::Local(folder) => write!(f, "{folder}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename = "metadata")]
 pub struct ModMetadata {
     pub name: String,
     pub directory: String,
+    /// Workshop id, 0 for mods that were never uploaded.
+    #[serde(default, skip_serializing_if = "is_unset_id")]
     pub id: u64,
     pub description: String,
     pub version: String,
     pub visibility: String,
-    #[serde(rename = "tag")]
+    #[serde(rename = "tag", skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<ModTag>>,
 }
 
-#[derive(Debug, Deserialize)]
+fn is_unset_id(id: &u64) -> bool {
+    *id == 0
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModTag {
     #[serde(rename = "@id")]
     pub id: ModTagId,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum ModTagId {
     Lua,
@@ -85,10 +237,156 @@ pub enum ModTagId {
     Api,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+impl ModTagId {
+    pub const ALL: [Self; 23] = [
+        Self::Lua,
+        Self::Items,
+        Self::ActiveItems,
+        Self::Trinkets,
+        Self::Pills,
+        Self::Cards,
+        Self::Pickups,
+        Self::PlayerCharacters,
+        Self::Familiars,
+        Self::Babies,
+        Self::Rooms,
+        Self::Floors,
+        Self::Enemies,
+        Self::Bosses,
+        Self::Hazards,
+        Self::Challenges,
+        Self::Tweaks,
+        Self::Removals,
+        Self::Graphics,
+        Self::Shaders,
+        Self::SoundEffects,
+        Self::Music,
+        Self::Api,
+    ];
+}
+
+impl std::fmt::Display for ModTagId {
+    /// Writes the tag the way `metadata.xml` spells it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Lua => "Lua",
+                Self::Items => "Items",
+                Self::ActiveItems => "Active Items",
+                Self::Trinkets => "Trinkets",
+                Self::Pills => "Pills",
+                Self::Cards => "Cards",
+                Self::Pickups => "Pickups",
+                Self::PlayerCharacters => "Player Characters",
+                Self::Familiars => "Familiars",
+                Self::Babies => "Babies",
+                Self::Rooms => "Rooms",
+                Self::Floors => "Floors",
+                Self::Enemies => "Enemies",
+                Self::Bosses => "Bosses",
+                Self::Hazards => "Hazards",
+                Self::Challenges => "Challenges",
+                Self::Tweaks => "Tweaks",
+                Self::Removals => "Removals",
+                Self::Graphics => "Graphics",
+                Self::Shaders => "Shaders",
+                Self::SoundEffects => "Sound Effects",
+                Self::Music => "Music",
+                Self::Api => "API",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// Mods folder of the default game instance.
     pub mods_path: PathBuf,
     pub theme: Theme,
+    /// Mods kept enabled regardless of the loaded profile.
+    #[serde(default)]
+    pub always_enabled: Vec<ModKey>,
+    /// Mods kept disabled regardless of the loaded profile.
+    #[serde(default)]
+    pub never_enabled: Vec<ModKey>,
+    /// Keep every mod tagged as an API enabled.
+    #[serde(default)]
+    pub always_enable_api_mods: bool,
+    /// Game log to look for errors in. The default location is used when empty.
+    #[serde(default)]
+    pub log_path: PathBuf,
+    /// Number of errors in the game log above which a mod is proposed for quarantine.
+    #[serde(default = "default_quarantine_threshold")]
+    pub quarantine_threshold: usize,
+    /// Steam folders searched for the game on top of the default locations.
+    #[serde(default)]
+    pub steam_roots: Vec<PathBuf>,
+    /// Keep a compressed copy of every new mod version seen, to roll back broken updates.
+    #[serde(default)]
+    pub archive_mods: bool,
+    /// Patterns of files left out of exported mod archives.
+    #[serde(default = "default_export_ignore")]
+    pub export_ignore: Vec<String>,
+    /// Game installations on top of the default one, each with its own mods folder and profiles.
+    #[serde(default)]
+    pub instances: Vec<GameInstance>,
+    /// Id of the instance being managed, 0 being the default one.
+    #[serde(default)]
+    pub current_instance: u32,
+    /// Highest instance id ever given out. Ids are not reused, as profiles and history files are
+    /// named after them.
+    #[serde(default)]
+    pub last_instance_id: u32,
+    /// How mods are disabled.
+    #[serde(default)]
+    pub disable_strategy: DisableStrategy,
+}
+
+/// A named game installation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameInstance {
+    pub id: u32,
+    pub name: String,
+    pub mods_path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceChoice {
+    pub id: u32,
+    pub name: String,
+}
+
+impl std::fmt::Display for InstanceChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+fn default_quarantine_threshold() -> usize {
+    20
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            mods_path: PathBuf::default(),
+            theme: Theme::default(),
+            always_enabled: Vec::new(),
+            never_enabled: Vec::new(),
+            always_enable_api_mods: false,
+            log_path: PathBuf::default(),
+            quarantine_threshold: default_quarantine_threshold(),
+            steam_roots: Vec::new(),
+            archive_mods: false,
+            export_ignore: default_export_ignore(),
+            instances: Vec::new(),
+            current_instance: 0,
+            last_instance_id: 0,
+            disable_strategy: DisableStrategy::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -115,7 +413,170 @@ impl std::fmt::Display for Theme {
     }
 }
 
+/// How mods are kept from loading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisableStrategy {
+    /// A `disable.it` file in the mod folder, which the game skips mods for.
+    #[default]
+    Marker,
+    /// Moving the mod folder next to the mods folder, so that the game does not scan it at all.
+    /// Only for local mods, as Steam downloads missing workshop mods again.
+    ParkingFolder,
+}
+
+impl DisableStrategy {
+    pub const ALL: [Self; 2] = [Self::Marker, Self::ParkingFolder];
+}
+
+impl std::fmt::Display for DisableStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Marker => "Add disable.it",
+                Self::ParkingFolder => "Move local mods to \"disabled mods\"",
+            }
+        )
+    }
+}
+
+/// Folder mods are parked in when disabled by moving them, next to the mods folder.
+pub fn parking_path(mods_path: &Path) -> PathBuf {
+    mods_path.with_file_name("disabled mods")
+}
+
 impl AppConfig {
+    /// Returns the state a mod is pinned to, if any. Never enabled pins take precedence.
+    pub fn pinned_state(&self, m: &Mod) -> Option<bool> {
+        let key = m.key();
+        if self.never_enabled.contains(&key) {
+            Some(false)
+        } else if self.always_enabled.contains(&key)
+            || (self.always_enable_api_mods && m.has_tag(ModTagId::Api))
+        {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Moves the pin of a mod over to another one.
+    pub fn replace_pin(&mut self, old: &ModKey, new: &ModKey) {
+        for pins in [&mut self.always_enabled, &mut self.never_enabled] {
+            if let Some(i) = pins.iter().position(|m| m == old) {
+                if pins.contains(new) {
+                    pins.remove(i);
+                } else {
+                    pins[i] = new.clone();
+                }
+            }
+        }
+    }
+
+    /// Cycles a mod between unpinned, always enabled and never enabled.
+    pub fn cycle_pin(&mut self, key: ModKey) {
+        if let Some(i) = self.always_enabled.iter().position(|m| *m == key) {
+            self.always_enabled.remove(i);
+            self.never_enabled.push(key);
+        } else if let Some(i) = self.never_enabled.iter().position(|m| *m == key) {
+            self.never_enabled.remove(i);
+        } else {
+            self.always_enabled.push(key);
+        }
+    }
+
+    /// Mods folder of the current instance.
+    pub fn get_mods_path(&self) -> &Path {
+        match self
+            .instances
+            .iter()
+            .find(|i| i.id == self.current_instance)
+        {
+            Some(instance) => &instance.mods_path,
+            None => &self.mods_path,
+        }
+    }
+
+    pub fn get_mods_path_mut(&mut self) -> &mut PathBuf {
+        let current = self.current_instance;
+        match self.instances.iter_mut().find(|i| i.id == current) {
+            Some(instance) => &mut instance.mods_path,
+            None => &mut self.mods_path,
+        }
+    }
+
+    pub fn get_instance_choices(&self) -> Vec<InstanceChoice> {
+        let mut choices = vec![InstanceChoice {
+            id: 0,
+            name: "<default>".to_string(),
+        }];
+        choices.extend(self.instances.iter().map(|i| InstanceChoice {
+            id: i.id,
+            name: i.name.clone(),
+        }));
+        choices
+    }
+
+    pub fn get_current_instance_choice(&self) -> Option<InstanceChoice> {
+        self.get_instance_choices()
+            .into_iter()
+            .find(|c| c.id == self.current_instance)
+    }
+
+    /// Selects an instance by name, ignoring case.
+    pub fn select_instance(&mut self, name: &str) -> anyhow::Result<()> {
+        let choice = self
+            .get_instance_choices()
+            .into_iter()
+            .find(|c| {
+                c.name.eq_ignore_ascii_case(name)
+                    || (c.id == 0 && name.eq_ignore_ascii_case("default"))
+            })
+            .ok_or_else(|| anyhow!("Unknown game instance: {name}"))?;
+        self.current_instance = choice.id;
+        Ok(())
+    }
+
+    /// Adds an instance with an empty mods folder and selects it.
+    pub fn add_instance(&mut self, name: String) {
+        let id = self
+            .instances
+            .iter()
+            .map(|i| i.id)
+            .fold(self.last_instance_id, u32::max)
+            + 1;
+        self.last_instance_id = id;
+        self.instances.push(GameInstance {
+            id,
+            name,
+            mods_path: PathBuf::default(),
+        });
+        self.current_instance = id;
+    }
+
+    /// Removes the current instance, falling back to the default one.
+    pub fn remove_current_instance(&mut self) {
+        let current = self.current_instance;
+        self.instances.retain(|i| i.id != current);
+        self.current_instance = 0;
+    }
+
+    /// Returns the configured game log path, or the location the game writes it to by default.
+    pub fn get_log_path(&self) -> Option<PathBuf> {
+        if !self.log_path.as_os_str().is_empty() {
+            return Some(self.log_path.clone());
+        }
+        let documents = directories::UserDirs::new()?.document_dir()?.to_path_buf();
+        [
+            "Binding of Isaac Repentance+",
+            "Binding of Isaac Repentance",
+        ]
+        .iter()
+        .map(|game| documents.join("My Games").join(game).join("log.txt"))
+        .find(|path| path.exists())
+    }
+
     pub fn load_or_default() -> Self {
         Self::load().unwrap_or_default()
     }
@@ -146,8 +607,160 @@ impl AppConfig {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct ModProfile {
     pub name: String,
-    pub enabled_mods: Vec<u64>,
+    /// Mods enabled by this profile. For a profile with a parent, these are added on top of the
+    /// parent's enabled mods.
+    pub enabled_mods: Vec<ModKey>,
+    /// Optional profile this one is layered on top of.
+    #[serde(default)]
+    pub parent: Option<i32>,
+    /// Mods enabled by the parent profile that this profile disables.
+    #[serde(default)]
+    pub disabled_mods: Vec<ModKey>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::util::test::{write_mod, TempDir};
+
+    use super::{parking_path, AppConfig, DisableStrategy, Mod};
+
+    fn marker_names(m: &Mod) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(&m.path).unwrap().flatten().map(|e| e.file_name().to_string_lossy().to_string()).filter(|n| n.eq_ignore_ascii_case("disable.it")).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn disable_marker_case() {
+        let dir = TempDir::new("disable");
+        let mods_path = dir.join("mods");
+        write_mod(&mods_path, "upper", "");
+        write_mod(&mods_path, "both", "");
+        fs::write(mods_path.join("upper/Disable.it"), "").unwrap();
+        fs::write(mods_path.join("both/DISABLE.IT"), "").unwrap();
+        fs::write(mods_path.join("both/disable.it"), "").unwrap();
+
+        let mut mods = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        mods.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        let [both, upper] = &mut mods[..] else { panic!("Both mods should load") };
+        assert!(!upper.enabled(), "Disable.it should disable the mod like the game does");
+        assert!(!both.enabled());
+
+        upper.set_enabled(false).unwrap();
+        assert_eq!(marker_names(upper), ["disable.it"], "Disabling should normalise the marker name");
+        both.set_enabled(true).unwrap();
+        assert!(both.enabled(), "Enabling should remove every spelling of the marker");
+        assert!(marker_names(both).is_empty());
+        both.set_enabled(true).unwrap();
+        both.set_enabled(false).unwrap();
+        assert!(!both.enabled());
+    }
+
+    #[test]
+    fn parking_strategy() {
+        let dir = TempDir::new("parking");
+        let mods_path = dir.join("mods");
+        write_mod(&mods_path, "parked", "");
+        write_mod(&mods_path, "marked", "");
+        fs::write(mods_path.join("marked/disable.it"), "").unwrap();
+
+        let mut mods = Mod::load_all(&mods_path, DisableStrategy::ParkingFolder).unwrap();
+        mods.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        let [marked, parked] = &mut mods[..] else { panic!("Both mods should load") };
+        parked.set_enabled(false).unwrap();
+        assert!(!parked.enabled() && parked.parked());
+        assert_eq!(parked.path, parking_path(&mods_path).join("parked"), "Disabled mods should be moved next to the mods folder");
+        marked.set_enabled(false).unwrap();
+        assert!(marked.parked(), "Mods disabled with a marker should be switched over");
+        assert!(marker_names(marked).is_empty(), "Parked mods do not need a marker");
+
+        let mut mods = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        assert_eq!(mods.len(), 2, "Parked mods should be listed with the others");
+        assert!(mods.iter().all(|m| !m.enabled()));
+        let m = &mut mods[0];
+        m.set_enabled(false).unwrap();
+        assert!(!m.parked() && !m.enabled(), "Switching back should keep the mod disabled with a marker");
+        assert_eq!(marker_names(m), ["disable.it"]);
+        mods[1].set_enabled(true).unwrap();
+        assert!(mods[1].enabled() && mods[1].path.starts_with(&mods_path), "Enabling should move parked mods back");
+    }
+
+    #[test]
+    fn parking_failure() {
+        let dir = TempDir::new("parking-failure");
+        let mods_path = dir.join("mods");
+        write_mod(&mods_path, "local", "");
+        write_mod(&parking_path(&mods_path), "local", "");
+
+        let mut mods = Mod::load_all(&mods_path, DisableStrategy::ParkingFolder).unwrap();
+        let m = mods.iter_mut().find(|m| !m.parked()).unwrap();
+        assert!(m.set_enabled(false).is_err(), "Parking over an existing folder should fail");
+        assert!(!m.enabled() && !m.parked(), "A mod that cannot be parked should still be disabled");
+        assert_eq!(marker_names(m), ["disable.it"]);
+
+        fs::remove_file(m.disable_path()).unwrap();
+        let m = mods.iter_mut().find(|m| m.parked()).unwrap();
+        assert!(m.set_enabled(true).is_err(), "Enabling over an existing folder should fail");
+        assert!(m.parked() && !m.enabled(), "A mod that cannot be moved back should stay parked");
+    }
+
+    #[test]
+    fn pins() {
+        let dir = TempDir::new("pins");
+        let mods_path = dir.join("mods");
+        write_mod(&mods_path, "plain", "");
+        write_mod(&mods_path, "api", r#"<tag id="API"/>"#);
+        let mut mods = Mod::load_all(&mods_path, DisableStrategy::Marker).unwrap();
+        mods.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        let [api, plain] = &mods[..] else { panic!("Both mods should load") };
+
+        let mut config = AppConfig::default();
+        assert_eq!(config.pinned_state(plain), None);
+        config.cycle_pin(plain.key());
+        assert_eq!(config.pinned_state(plain), Some(true), "Pins should start as always enabled");
+        config.cycle_pin(plain.key());
+        assert_eq!(config.pinned_state(plain), Some(false));
+        assert!(config.always_enabled.is_empty(), "A mod should only be pinned one way");
+        config.cycle_pin(plain.key());
+        assert_eq!(config.pinned_state(plain), None);
+        assert!(config.never_enabled.is_empty());
+
+        assert_eq!(config.pinned_state(api), None);
+        config.always_enable_api_mods = true;
+        assert_eq!(config.pinned_state(api), Some(true), "API mods should be pinned when asked to");
+        config.never_enabled.push(api.key());
+        assert_eq!(config.pinned_state(api), Some(false), "Never enabled should win over API pins");
+        config.always_enabled.push(plain.key());
+        config.never_enabled.push(plain.key());
+        assert_eq!(config.pinned_state(plain), Some(false), "Never enabled should win over always enabled");
+    }
+
+    #[test]
+    fn game_instances() {
+        let mut config = AppConfig { mods_path: PathBuf::from("/games/isaac/mods"), ..Default::default() };
+        config.add_instance("Repentance+".to_string());
+        *config.get_mods_path_mut() = PathBuf::from("/games/isaac+/mods");
+        assert_eq!(config.current_instance, 1, "A new instance should be selected");
+        assert_eq!(config.mods_path, Path::new("/games/isaac/mods"), "The default instance should keep its mods folder");
+
+        config.select_instance("default").unwrap();
+        assert_eq!(config.get_mods_path(), Path::new("/games/isaac/mods"));
+        config.select_instance("repentance+").unwrap();
+        assert_eq!(config.get_mods_path(), Path::new("/games/isaac+/mods"), "Instances should be selected ignoring case");
+        assert!(config.select_instance("Afterbirth").is_err());
+
+        config.remove_current_instance();
+        assert_eq!(config.current_instance, 0, "Removing an instance should fall back to the default one");
+        assert!(config.instances.is_empty());
+        config.add_instance("Repentance".to_string());
+        assert_eq!(config.current_instance, 2, "Ids of removed instances should not be reused");
+    }
 }
@@ -1,34 +1,120 @@
-use std::fs;
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::anyhow;
 use iced::{
     alignment::{Horizontal, Vertical},
+    executor, subscription, time,
     widget::{checkbox, column, container, pick_list, row, scrollable, text, text_input},
-    Alignment, Element, Length, Sandbox,
+    window, Alignment, Application, Color, Command, Element, Event, Length, Subscription,
 };
 use rfd::FileDialog;
 
 use crate::{
+    archive::{ArchivedVersion, ModArchive},
+    conflicts::{find_override_conflicts, OverrideConflict},
+    content::{find_content_collisions, ContentCollision, ContentFacet},
+    doctor::{apply_safe_fixes, diagnose, Diagnosis},
+    export::{export_file_name, export_mod},
+    history::{ChangeReport, ScanHistory},
+    install::{clean_stale_staging, InstallPlan},
+    log::{LineLevel, LogReport, LogTail},
+    lua::{infer_dependencies, InferredDependency, LuaScan},
     manager::{ModProfileManager, ModProfileState},
-    types::{AppConfig, Mod, Theme},
+    metadata::{bump_version, write_metadata, VersionPart},
+    quarantine::{Quarantine, QuarantineEntry},
+    rules::{ModRule, ModRules, RuleKind, RuleReport},
+    scaffold::{directory_from_name, NewMod, VISIBILITIES},
+    steam::{detect_installs, GameInstall, Workshop, WorkshopStatus},
+    trash::Trash,
+    types::{
+        AppConfig, DisableStrategy, InstanceChoice, Mod, ModKey, ModMetadata, ModTag, ModTagId,
+        Theme,
+    },
+    validate::{validate_mod, Severity, ValidationReport},
 };
 
+const GAME_LAUNCH_URL: &str = "steam://rungameid/250900";
+const LOG_SCROLLABLE: &str = "live-log";
+
 pub struct ModManager {
     mod_list: Vec<Mod>,
     state: AppState,
     config: AppConfig,
     current_theme: Option<Theme>,
     profile_manager: ModProfileManager,
+    rules: ModRules,
+    rule_report: RuleReport,
+    facet_filter: ContentFacet,
+    log_tail: Option<LogTail>,
+    quarantine: Quarantine,
+    detected_installs: Vec<GameInstall>,
+    workshop: Option<Workshop>,
+    history: ScanHistory,
+    change_report: ChangeReport,
+    archive: ModArchive,
+    trash: Trash,
+    /// Lua scans of the mods, in the order of the mod list. Taken on first use after a refresh.
+    lua_scans: Vec<LuaScan>,
+    /// Conflicts with the enabled mods they were found for, kept until a refresh.
+    conflict_cache: Option<(Vec<ModKey>, Vec<OverrideConflict>, Vec<ContentCollision>)>,
+    /// Mod folders waiting to be archived, with the versions they will be recorded as.
+    archive_queue: Vec<(PathBuf, ArchivedVersion)>,
+    /// Whether mods are being archived in the background.
+    archiving: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     // Mod list entries
     Toggle(usize, bool),
+    CyclePin(usize),
+    OpenModDetails(usize),
+    OpenConflicts,
+    OpenChanges,
+    InstallFromZip,
+    InstallFromFolder,
+    FileDropped(PathBuf),
+    ConfirmInstall,
+    CancelInstall,
+    OpenGameLog,
+    QuarantineMod(ModKey),
+    QuarantineAll,
+    ReleaseMod(ModKey),
+    OpenLiveLog,
+    PollLog,
+    FilterLog(ModChoice),
+    ClearLogFilter,
+    OpenModDetailsByKey(ModKey),
+    SelectFacet(ContentFacet),
+    SwitchInstance(InstanceChoice),
     Refresh,
     EnableAll,
     DisableAll,
 
+    // New mod wizard
+    OpenNewMod,
+    EditNewModName(String),
+    EditNewModDirectory(String),
+    EditNewModDescription(String),
+    EditNewModVersion(String),
+    SelectNewModVisibility(&'static str),
+    ToggleNewModTag(ModTagId, bool),
+    SwitchNewModContent(bool),
+    SwitchNewModResources(bool),
+    CreateNewMod,
+
+    // Metadata editor
+    EditMetadata,
+    EditMetadataName(String),
+    EditMetadataDescription(String),
+    EditMetadataVersion(String),
+    BumpVersion(VersionPart),
+    SelectMetadataVisibility(&'static str),
+    ToggleMetadataTag(ModTagId, bool),
+    SaveMetadata,
+
     // Quick mod profile management
     SelectProfile(ModProfileState),
     LoadProfile,
@@ -37,9 +123,39 @@ pub enum Message {
 
     // Advanced profile management
     OnProfileNameEdit(String),
+    SelectParentProfile(ModProfileState),
     CreateNewProfile,
     DeleteCurrentProfile,
 
+    // Mod rules
+    ManageRules,
+    SelectRuleMod(ModChoice),
+    SelectRuleKind(RuleKind),
+    SelectRuleOther(ModChoice),
+    AddRule,
+    RemoveRule(usize),
+    EnableDependencies,
+    ConfirmPendingAction,
+    AddInferredRule(ModKey),
+    AddAllInferredRules,
+
+    // Mod details actions
+    RollbackMod(usize),
+    UninstallMod,
+    ExportMod,
+    ValidateMod,
+
+    // Trash
+    OpenTrash,
+    RestoreMod(usize),
+    PurgeMod(usize),
+    ConfirmPurge(usize),
+
+    // Doctor
+    OpenDoctor,
+    ApplyFix(usize),
+    ApplyAllFixes,
+
     // Navigation
     OpenConfig,
     ReturnToModList,
@@ -48,62 +164,301 @@ pub enum Message {
     // Config related entries
     SaveConfig,
     SelectGamePath,
+    SelectConfigInstance(InstanceChoice),
+    EditInstanceName(String),
+    AddInstance,
+    RemoveInstance,
+    DetectGamePath,
+    UseDetectedPath(usize),
+    AddSteamRoot,
+    RemoveSteamRoot(usize),
     SwitchTheme(Theme),
+    SwitchApiPin(bool),
+    SwitchArchiveMods(bool),
+    SwitchDisableStrategy(DisableStrategy),
+    EditExportIgnore(String),
+    SelectLogPath,
+    EditQuarantineThreshold(String),
+
+    // Mod archive
+    ArchiveDone(Vec<ArchivedVersion>, Vec<String>),
 
     // Misc
+    LaunchGame,
     ActionOpen(String),
 }
 
 #[derive(Debug, Clone)]
 pub enum AppState {
     ModList,
-    Profiles { temp_profile_name: String },
+    Profiles {
+        temp_profile_name: String,
+    },
+    Rules {
+        rule_mod: Option<ModChoice>,
+        rule_kind: RuleKind,
+        rule_other: Option<ModChoice>,
+    },
+    RuleCheck(PendingAction),
+    ModDetails {
+        index: usize,
+        dependencies: Vec<InferredDependency>,
+    },
+    Conflicts {
+        overrides: Vec<OverrideConflict>,
+        collisions: Vec<ContentCollision>,
+    },
+    Changes,
+    Trash,
+    PurgeCheck(usize),
+    Doctor(Result<Vec<Diagnosis>, String>),
+    Install(Result<InstallPlan, String>),
+    NewMod {
+        new_mod: NewMod,
+        error: Option<String>,
+    },
+    EditMetadata {
+        index: usize,
+        metadata: ModMetadata,
+        error: Option<String>,
+    },
+    Validation {
+        index: usize,
+        report: ValidationReport,
+    },
+    GameLog(Result<LogReport, String>),
+    LiveLog {
+        filter: Option<ModChoice>,
+        error: Option<String>,
+    },
     Config(AppConfig),
     About,
 }
 
+/// Action waiting for the user to acknowledge broken mod rules.
+#[derive(Debug, Clone, Copy)]
+pub enum PendingAction {
+    SaveProfile,
+    LaunchGame,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModChoice {
+    pub key: ModKey,
+    pub name: String,
+}
+
+impl std::fmt::Display for ModChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 impl ModManager {
+    /// Shows what installing a zip or a folder would do, or why it cannot be installed.
+    fn plan_install(&mut self, source: &Path) {
+        let plan = InstallPlan::new(source, self.config.get_mods_path(), &self.mod_list);
+        self.state = AppState::Install(plan.map_err(|e| e.to_string()));
+    }
+
     fn refresh_mods(&mut self) -> anyhow::Result<()> {
-        if self.config.mods_path.as_os_str().is_empty() || self.config.mods_path.is_relative() {
-            return Err(anyhow!("Invalid mod path set!"));
+        self.workshop = Workshop::from_mods_path(self.config.get_mods_path()).ok();
+        self.lua_scans.clear();
+        self.conflict_cache = None;
+        self.mod_list = Mod::load_all(self.config.get_mods_path(), self.config.disable_strategy)?;
+        // Steam downloads missing workshop mods again, so only local mods are parked
+        let local = Workshop::default();
+        let workshop = self.workshop.as_ref().unwrap_or(&local);
+        for m in self.mod_list.iter_mut() {
+            if workshop.status(m) != WorkshopStatus::LocalOnly {
+                m.strategy = DisableStrategy::Marker;
+            }
+        }
+        let scan = self.history.scan(&self.mod_list);
+        if self.config.archive_mods {
+            self.archive_queue = self.archive.pending(&self.mod_list, &scan);
+        }
+        // Changes found earlier stay on display until the next scan finds new ones
+        let report = self.history.record(scan);
+        if !report.changes.is_empty() {
+            self.change_report = report;
+        }
+        let _ = self.history.save();
+        Ok(())
+    }
+
+    /// Archives the queued mod folders in the background, unless it is already busy doing so.
+    fn archive_queued(&mut self) -> Command<Message> {
+        let archive = &self.archive;
+        self.archive_queue.retain(|(_, v)| !archive.contains(v));
+        if self.archiving || self.archive_queue.is_empty() {
+            return Command::none();
+        }
+        self.archiving = true;
+        let pending = std::mem::take(&mut self.archive_queue);
+        Command::perform(
+            async move { ModArchive::archive(pending) },
+            |(versions, errors)| Message::ArchiveDone(versions, errors),
+        )
+    }
+
+    /// Enables or disables every mod matching the predicate, keeping pinned mods in their state.
+    fn apply_enabled(&mut self, enabled: impl Fn(&Mod) -> bool) {
+        for m in self.mod_list.iter_mut() {
+            let enabled = !self.quarantine.contains(&m.key())
+                && self.config.pinned_state(m).unwrap_or_else(|| enabled(m));
+            if m.enabled() != enabled {
+                let _ = m.set_enabled(enabled);
+            }
         }
-        let mut mods = Vec::new();
-        for entry in fs::read_dir(&self.config.mods_path)?.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                match Mod::from_path(path) {
-                    Ok(m) => mods.push(m),
-                    Err(e) => println!("Error loading mod: {e}"),
+    }
+
+    /// Disables the mods proposed for quarantine by the game log report matching the predicate.
+    fn quarantine_mods(&mut self, selected: impl Fn(&QuarantineEntry) -> bool) {
+        let AppState::GameLog(Ok(report)) = &self.state else {
+            return;
+        };
+        let proposed = self
+            .quarantine
+            .propose(report, self.config.quarantine_threshold);
+        for entry in proposed.into_iter().filter(|e| selected(e)) {
+            if let Some(m) = self.mod_list.iter_mut().find(|m| m.key() == entry.mod_id) {
+                if m.enabled() {
+                    let _ = m.set_enabled(false);
                 }
             }
+            self.quarantine.add(entry);
         }
-        self.mod_list = mods;
-        Ok(())
+        let _ = self.quarantine.save();
+        self.check_rules();
     }
 
-    fn get_enabled_mod_ids(&self) -> Vec<u64> {
+    fn get_enabled_mod_keys(&self) -> Vec<ModKey> {
         self.mod_list
             .iter()
             .filter(|m| m.enabled())
-            .map(|m| m.metadata.id)
+            .map(Mod::key)
+            .collect()
+    }
+
+    fn check_rules(&mut self) {
+        self.rule_report = self.rules.check(&self.get_enabled_mod_keys());
+    }
+
+    fn get_mod_name(&self, key: &ModKey) -> String {
+        self.mod_list
+            .iter()
+            .find(|m| m.key() == *key)
+            .map(|m| m.metadata.name.clone())
+            .unwrap_or_else(|| format!("<missing mod {key}>"))
+    }
+
+    fn get_mod_choices(&self) -> Vec<ModChoice> {
+        self.mod_list
+            .iter()
+            .map(|m| ModChoice {
+                key: m.key(),
+                name: m.metadata.name.clone(),
+            })
             .collect()
     }
+
+    /// Runs an action, or asks for confirmation first if the enabled mods break a rule.
+    fn run_checked(&mut self, action: PendingAction) {
+        self.check_rules();
+        if self.rule_report.is_empty() {
+            self.run_action(action);
+        } else {
+            self.state = AppState::RuleCheck(action);
+        }
+    }
+
+    fn run_action(&mut self, action: PendingAction) {
+        match action {
+            PendingAction::SaveProfile => {
+                let enabled_mods = self.get_enabled_mod_keys();
+                self.profile_manager.update_current_profile(enabled_mods);
+                let _ = self.profile_manager.save();
+            }
+            PendingAction::LaunchGame => {
+                let _ = open::that_detached(GAME_LAUNCH_URL);
+            }
+        }
+    }
+
+    fn rule_report_view(&self) -> iced::widget::Column<'_, Message> {
+        let missing = self.rule_report.missing.iter().map(|(m, d)| {
+            let line = format!(
+                "{} requires {}, which is disabled",
+                self.get_mod_name(m),
+                self.get_mod_name(d)
+            );
+            text(line).into()
+        });
+        let conflicts = self.rule_report.conflicts.iter().map(|(m, c)| {
+            let line = format!(
+                "{} conflicts with {}",
+                self.get_mod_name(m),
+                self.get_mod_name(c)
+            );
+            text(line).into()
+        });
+        let mut report = column(missing.chain(conflicts).collect()).spacing(5);
+        if !self.rule_report.missing.is_empty() {
+            report = report.push(
+                button("ENABLE DEPENDENCIES")
+                    .on_press(Message::EnableDependencies)
+                    .width(200),
+            );
+        }
+        report
+    }
 }
 
-impl Sandbox for ModManager {
+impl Application for ModManager {
+    type Executor = executor::Default;
     type Message = Message;
+    type Theme = iced::Theme;
+    type Flags = ();
 
-    fn new() -> Self {
+    fn new(_flags: ()) -> (Self, Command<Message>) {
+        clean_stale_staging();
+        let config = AppConfig::load_or_default();
         let mut manager = Self {
             mod_list: Default::default(),
             state: AppState::ModList,
-            config: AppConfig::load_or_default(),
             current_theme: None,
-            profile_manager: ModProfileManager::load_or_default(),
+            profile_manager: ModProfileManager::load_or_default(config.current_instance),
+            rules: ModRules::load_or_default(),
+            rule_report: RuleReport::default(),
+            facet_filter: ContentFacet::default(),
+            log_tail: None,
+            quarantine: Quarantine::load_or_default(),
+            detected_installs: Vec::new(),
+            workshop: None,
+            history: ScanHistory::load_or_default(config.current_instance),
+            change_report: ChangeReport::default(),
+            archive: ModArchive::load_or_default(),
+            trash: Trash::load_or_default(),
+            lua_scans: Vec::new(),
+            conflict_cache: None,
+            archive_queue: Vec::new(),
+            archiving: false,
+            config,
         };
         manager.current_theme = Some(manager.config.theme);
+        if manager.config.get_mods_path().as_os_str().is_empty() {
+            // First run: offer the mods folders found in Steam libraries
+            manager.detected_installs = detect_installs(&manager.config.steam_roots);
+            manager.state = AppState::Config(manager.config.clone());
+        }
         let _ = manager.refresh_mods();
-        manager
+        manager.check_rules();
+        if matches!(manager.state, AppState::ModList) && !manager.change_report.changes.is_empty() {
+            manager.state = AppState::Changes;
+        }
+        let command = manager.archive_queued();
+        (manager, command)
     }
 
     fn title(&self) -> String {
@@ -117,27 +472,339 @@ impl Sandbox for ModManager {
         }
     }
 
-    fn update(&mut self, message: Message) {
+    fn subscription(&self) -> Subscription<Message> {
+        match self.state {
+            AppState::LiveLog { .. } => {
+                time::every(time::Duration::from_millis(500)).map(|_| Message::PollLog)
+            }
+            AppState::ModList => subscription::events_with(|event, _| match event {
+                Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+                _ => None,
+            }),
+            _ => Subscription::none(),
+        }
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
         // TODO: find better way to handle (and display) errors here
         // TODO: REALLY find a better way to handle errors
         // TODO: REALLY REALLY REALLY FIND A WAY TO ACTUALLY HANDLE ERRORS
         match message {
             // Mod list
             Message::Toggle(i, b) => {
-                let _ = self.mod_list.get_mut(i).map(|m| m.set_enabled(b));
+                if let Some(m) = self.mod_list.get_mut(i) {
+                    if self.config.pinned_state(m).is_none() {
+                        let _ = m.set_enabled(b);
+                    }
+                }
+                self.check_rules();
+            }
+            Message::CyclePin(i) => {
+                if let Some(m) = self.mod_list.get_mut(i) {
+                    self.config.cycle_pin(m.key());
+                    if let Some(enabled) = self.config.pinned_state(m) {
+                        if m.enabled() != enabled {
+                            let _ = m.set_enabled(enabled);
+                        }
+                    }
+                    let _ = self.config.save();
+                }
+                self.check_rules();
+            }
+            Message::OpenModDetails(i) => {
+                if i < self.mod_list.len() {
+                    if self.lua_scans.len() != self.mod_list.len() {
+                        self.lua_scans = self.mod_list.iter().map(LuaScan::from_mod).collect();
+                    }
+                    self.state = AppState::ModDetails {
+                        index: i,
+                        dependencies: infer_dependencies(i, &self.mod_list, &self.lua_scans),
+                    };
+                }
+            }
+            Message::OpenConflicts => {
+                let enabled = self.get_enabled_mod_keys();
+                let cache = match self.conflict_cache.take() {
+                    Some(cache) if cache.0 == enabled => cache,
+                    _ => (
+                        enabled,
+                        find_override_conflicts(&self.mod_list),
+                        find_content_collisions(&self.mod_list),
+                    ),
+                };
+                let (_, overrides, collisions) = self.conflict_cache.insert(cache);
+                self.state = AppState::Conflicts {
+                    overrides: overrides.clone(),
+                    collisions: collisions.clone(),
+                };
+            }
+            Message::OpenChanges => {
+                let _ = self.refresh_mods();
+                self.check_rules();
+                self.state = AppState::Changes;
+            }
+            Message::InstallFromZip => {
+                if let Some(file) = FileDialog::new().add_filter("Zip", &["zip"]).pick_file() {
+                    self.plan_install(&file);
+                }
+            }
+            Message::InstallFromFolder => {
+                if let Some(folder) = FileDialog::new().pick_folder() {
+                    self.plan_install(&folder);
+                }
+            }
+            Message::FileDropped(path) => {
+                // Several files dropped at once send an event each, only the first one is kept
+                if let AppState::ModList = self.state {
+                    self.plan_install(&path);
+                }
+            }
+            Message::OpenNewMod => {
+                self.state = AppState::NewMod {
+                    new_mod: NewMod::default(),
+                    error: None,
+                }
+            }
+            Message::EditNewModName(name) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    // Keep the directory in sync with the name until it is edited by hand
+                    if new_mod.directory == directory_from_name(&new_mod.name) {
+                        new_mod.directory = directory_from_name(&name);
+                    }
+                    new_mod.name = name;
+                }
+            }
+            Message::EditNewModDirectory(directory) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.directory = directory;
+                }
+            }
+            Message::EditNewModDescription(description) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.description = description;
+                }
+            }
+            Message::EditNewModVersion(version) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.version = version;
+                }
+            }
+            Message::SelectNewModVisibility(visibility) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.visibility = visibility;
+                }
+            }
+            Message::ToggleNewModTag(tag, b) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.tags.retain(|t| *t != tag);
+                    if b {
+                        new_mod.tags.push(tag);
+                    }
+                }
+            }
+            Message::SwitchNewModContent(b) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.with_content = b;
+                }
+            }
+            Message::SwitchNewModResources(b) => {
+                if let AppState::NewMod { new_mod, .. } = &mut self.state {
+                    new_mod.with_resources = b;
+                }
+            }
+            Message::CreateNewMod => {
+                if let AppState::NewMod { new_mod, error } = &mut self.state {
+                    match new_mod.create(self.config.get_mods_path(), &self.mod_list) {
+                        Ok(_) => {
+                            self.state = AppState::ModList;
+                            let _ = self.refresh_mods();
+                            self.check_rules();
+                        }
+                        Err(e) => *error = Some(e.to_string()),
+                    }
+                }
+            }
+            Message::EditMetadata => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get(index) {
+                        self.state = AppState::EditMetadata {
+                            index,
+                            metadata: m.metadata.clone(),
+                            error: None,
+                        };
+                    }
+                }
+            }
+            Message::EditMetadataName(name) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    metadata.name = name;
+                }
+            }
+            Message::EditMetadataDescription(description) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    metadata.description = description;
+                }
+            }
+            Message::EditMetadataVersion(version) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    metadata.version = version;
+                }
+            }
+            Message::BumpVersion(part) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    metadata.version = bump_version(&metadata.version, part);
+                }
+            }
+            Message::SelectMetadataVisibility(visibility) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    metadata.visibility = visibility.to_string();
+                }
+            }
+            Message::ToggleMetadataTag(tag, b) => {
+                if let AppState::EditMetadata { metadata, .. } = &mut self.state {
+                    let tags = metadata.tags.get_or_insert_with(Vec::new);
+                    tags.retain(|t| t.id != tag);
+                    if b {
+                        tags.push(ModTag { id: tag });
+                    }
+                }
+            }
+            Message::SaveMetadata => {
+                if let AppState::EditMetadata {
+                    index,
+                    metadata,
+                    error,
+                } = &mut self.state
+                {
+                    let path = self.mod_list.get(*index).map(|m| m.path.clone());
+                    let result = path
+                        .ok_or_else(|| anyhow::anyhow!("This mod no longer exists"))
+                        .and_then(|path| write_metadata(&path, metadata).map(|_| path));
+                    match result {
+                        Ok(path) => {
+                            let _ = self.refresh_mods();
+                            self.check_rules();
+                            // Refreshing sorts the mods again, so the index may point elsewhere
+                            self.state = AppState::ModList;
+                            if let Some(i) = self.mod_list.iter().position(|m| m.path == path) {
+                                return self.update(Message::OpenModDetails(i));
+                            }
+                        }
+                        Err(e) => *error = Some(e.to_string()),
+                    }
+                }
+            }
+            Message::ConfirmInstall => {
+                if let AppState::Install(Ok(plan)) =
+                    std::mem::replace(&mut self.state, AppState::ModList)
+                {
+                    if let Err(e) = plan.apply(self.config.get_mods_path()) {
+                        self.state = AppState::Install(Err(e.to_string()));
+                    }
+                    let _ = self.refresh_mods();
+                    self.check_rules();
+                }
+            }
+            Message::CancelInstall => {
+                if let AppState::Install(Ok(plan)) =
+                    std::mem::replace(&mut self.state, AppState::ModList)
+                {
+                    plan.cancel();
+                }
+            }
+            Message::OpenGameLog => {
+                let report = match self.config.get_log_path() {
+                    Some(path) => {
+                        LogReport::from_file(&path, &self.mod_list).map_err(|e| e.to_string())
+                    }
+                    None => Err("Cannot find the game log, set its path in the settings".into()),
+                };
+                self.state = AppState::GameLog(report);
+            }
+            Message::QuarantineMod(key) => self.quarantine_mods(|e| e.mod_id == key),
+            Message::QuarantineAll => self.quarantine_mods(|_| true),
+            Message::ReleaseMod(key) => {
+                self.quarantine.release(&key);
+                let _ = self.quarantine.save();
+                if let Some(m) = self.mod_list.iter_mut().find(|m| m.key() == key) {
+                    // Mods pinned as never enabled stay disabled once released
+                    if !m.enabled() && self.config.pinned_state(m).unwrap_or(true) {
+                        let _ = m.set_enabled(true);
+                    }
+                }
+                self.check_rules();
+            }
+            Message::OpenLiveLog => {
+                self.log_tail = self.config.get_log_path().map(LogTail::new);
+                let error = match &mut self.log_tail {
+                    Some(tail) => tail.poll(&self.mod_list).err().map(|e| e.to_string()),
+                    None => Some("Cannot find the game log, set its path in the settings".into()),
+                };
+                self.state = AppState::LiveLog {
+                    filter: None,
+                    error,
+                };
+            }
+            Message::PollLog => {
+                // The game may not have written its log yet when the view was opened
+                if self.log_tail.is_none() {
+                    self.log_tail = self.config.get_log_path().map(LogTail::new);
+                }
+                if let (Some(tail), AppState::LiveLog { error, .. }) =
+                    (&mut self.log_tail, &mut self.state)
+                {
+                    match tail.poll(&self.mod_list) {
+                        Ok(new_lines) => {
+                            *error = None;
+                            if new_lines {
+                                return scrollable::snap_to(
+                                    scrollable::Id::new(LOG_SCROLLABLE),
+                                    scrollable::RelativeOffset::END,
+                                );
+                            }
+                        }
+                        Err(e) => *error = Some(e.to_string()),
+                    }
+                }
+            }
+            Message::FilterLog(choice) => {
+                if let AppState::LiveLog { filter, .. } = &mut self.state {
+                    *filter = Some(choice);
+                }
+            }
+            Message::ClearLogFilter => {
+                if let AppState::LiveLog { filter, .. } = &mut self.state {
+                    *filter = None;
+                }
+            }
+            Message::OpenModDetailsByKey(key) => {
+                if let Some(i) = self.mod_list.iter().position(|m| m.key() == key) {
+                    return self.update(Message::OpenModDetails(i));
+                }
+            }
+            Message::SelectFacet(facet) => self.facet_filter = facet,
+            Message::SwitchInstance(instance) => {
+                self.config.current_instance = instance.id;
+                let _ = self.config.save();
+                self.profile_manager = ModProfileManager::load_or_default(instance.id);
+                self.history = ScanHistory::load_or_default(instance.id);
+                if let Err(e) = self.refresh_mods() {
+                    println!("Error loading mods of {instance}: {e}");
+                    self.mod_list.clear();
+                }
+                self.check_rules();
             }
             Message::Refresh => {
                 let _ = self.refresh_mods();
+                self.check_rules();
             }
             Message::EnableAll => {
-                for m in self.mod_list.iter_mut() {
-                    let _ = m.set_enabled(true);
-                }
+                self.apply_enabled(|_| true);
+                self.check_rules();
             }
             Message::DisableAll => {
-                for m in self.mod_list.iter_mut() {
-                    let _ = m.set_enabled(false);
-                }
+                self.apply_enabled(|_| false);
+                self.check_rules();
             }
             // Mod profile management
             Message::SelectProfile(profile) => {
@@ -146,18 +813,15 @@ impl Sandbox for ModManager {
                 println!("{:#?}", self.profile_manager.current_profile_state);
             }
             Message::LoadProfile => {
-                if let Some(profile) = self.profile_manager.get_current_profile() {
-                    self.mod_list.iter_mut().for_each(|m| {
-                        let enabled = profile.enabled_mods.contains(&m.metadata.id);
-                        let _ = m.set_enabled(enabled);
-                    });
+                if self.profile_manager.get_current_profile().is_some() {
+                    match self.profile_manager.resolve_current_profile() {
+                        Ok(enabled_mods) => self.apply_enabled(|m| enabled_mods.contains(&m.key())),
+                        Err(e) => println!("Error loading profile: {e}"),
+                    }
+                    self.check_rules();
                 }
             }
-            Message::SaveProfile => {
-                let enabled_mods = self.get_enabled_mod_ids();
-                self.profile_manager.update_current_profile(enabled_mods);
-                let _ = self.profile_manager.save();
-            }
+            Message::SaveProfile => self.run_checked(PendingAction::SaveProfile),
             Message::ManageProfiles => {
                 self.state = AppState::Profiles {
                     temp_profile_name: String::new(),
@@ -169,6 +833,15 @@ impl Sandbox for ModManager {
                     *temp_profile_name = name;
                 }
             }
+            Message::SelectParentProfile(parent) => {
+                let parent = Some(parent.id).filter(|id| *id != 0);
+                match self.profile_manager.set_current_profile_parent(parent) {
+                    Ok(()) => {
+                        let _ = self.profile_manager.save();
+                    }
+                    Err(e) => println!("Error setting parent profile: {e}"),
+                }
+            }
             Message::CreateNewProfile => {
                 if let AppState::Profiles { temp_profile_name } = &mut self.state {
                     self.profile_manager
@@ -182,24 +855,296 @@ impl Sandbox for ModManager {
                     let _ = self.profile_manager.save();
                 }
             }
+            // Mod rules
+            Message::ManageRules => {
+                self.state = AppState::Rules {
+                    rule_mod: None,
+                    rule_kind: RuleKind::default(),
+                    rule_other: None,
+                };
+            }
+            Message::SelectRuleMod(choice) => {
+                if let AppState::Rules { rule_mod, .. } = &mut self.state {
+                    *rule_mod = Some(choice);
+                }
+            }
+            Message::SelectRuleKind(kind) => {
+                if let AppState::Rules { rule_kind, .. } = &mut self.state {
+                    *rule_kind = kind;
+                }
+            }
+            Message::SelectRuleOther(choice) => {
+                if let AppState::Rules { rule_other, .. } = &mut self.state {
+                    *rule_other = Some(choice);
+                }
+            }
+            Message::AddRule => {
+                if let AppState::Rules {
+                    rule_mod: Some(rule_mod),
+                    rule_kind,
+                    rule_other: Some(rule_other),
+                } = &self.state
+                {
+                    self.rules.add(ModRule {
+                        mod_id: rule_mod.key.clone(),
+                        kind: *rule_kind,
+                        other_id: rule_other.key.clone(),
+                    });
+                    let _ = self.rules.save();
+                    self.check_rules();
+                }
+            }
+            Message::RemoveRule(i) => {
+                self.rules.remove(i);
+                let _ = self.rules.save();
+                self.check_rules();
+            }
+            Message::EnableDependencies => {
+                let dependencies = self
+                    .rules
+                    .missing_dependencies(&self.get_enabled_mod_keys());
+                self.apply_enabled(|m| m.enabled() || dependencies.contains(&m.key()));
+                self.check_rules();
+            }
+            Message::AddInferredRule(other_id) => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get(index) {
+                        self.rules.add(ModRule {
+                            mod_id: m.key(),
+                            kind: RuleKind::Requires,
+                            other_id,
+                        });
+                        let _ = self.rules.save();
+                        self.check_rules();
+                    }
+                }
+            }
+            Message::RollbackMod(version) => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get_mut(index) {
+                        let restored =
+                            self.archive
+                                .restore(version, self.config.get_mods_path(), m);
+                        match restored.and_then(Mod::from_path) {
+                            Ok(mut copy) => {
+                                // The copy stands in for the original wherever it was referred to
+                                let (original, replacement) = (m.key(), copy.key());
+                                self.profile_manager.replace_mod(&original, &replacement);
+                                let _ = self.profile_manager.save();
+                                self.config.replace_pin(&original, &replacement);
+                                let _ = self.config.save();
+                                self.rules.replace_mod(&original, &replacement);
+                                let _ = self.rules.save();
+                                let _ = m.set_enabled(false);
+                                let _ = copy.set_enabled(true);
+                                let _ = self.refresh_mods();
+                                self.check_rules();
+                                self.state = AppState::ModList;
+                            }
+                            Err(e) => println!("Error rolling back mod: {e}"),
+                        }
+                    }
+                }
+            }
+            Message::UninstallMod => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get(index) {
+                        let profiles = self.profile_manager.get_profiles_enabling(&m.key());
+                        match self.trash.uninstall(m, profiles) {
+                            Ok(()) => {
+                                let _ = self.trash.save();
+                                let _ = self.refresh_mods();
+                                self.check_rules();
+                                self.state = AppState::ModList;
+                            }
+                            Err(e) => println!("Error uninstalling mod: {e}"),
+                        }
+                    }
+                }
+            }
+            Message::ExportMod => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get(index) {
+                        let target = FileDialog::new()
+                            .set_file_name(export_file_name(m))
+                            .add_filter("Zip", &["zip"])
+                            .save_file();
+                        if let Some(target) = target {
+                            if let Err(e) = export_mod(m, &target, &self.config.export_ignore) {
+                                println!("Error exporting mod: {e}");
+                            }
+                        }
+                    }
+                }
+            }
+            Message::ValidateMod => {
+                if let AppState::ModDetails { index, .. } = self.state {
+                    if let Some(m) = self.mod_list.get(index) {
+                        self.state = AppState::Validation {
+                            index,
+                            report: validate_mod(&m.path, &self.config.export_ignore),
+                        };
+                    }
+                }
+            }
+            Message::OpenTrash => self.state = AppState::Trash,
+            Message::OpenDoctor => {
+                self.state = AppState::Doctor(
+                    diagnose(self.config.get_mods_path()).map_err(|e| e.to_string()),
+                );
+            }
+            Message::ApplyFix(i) => {
+                if let AppState::Doctor(Ok(diagnoses)) = &self.state {
+                    if let Some(fix) = diagnoses.get(i).and_then(|d| d.fix.as_ref()) {
+                        if let Err(e) = fix.apply() {
+                            println!("Error applying fix: {e}");
+                        }
+                    }
+                    let _ = self.refresh_mods();
+                    self.check_rules();
+                    return self.update(Message::OpenDoctor);
+                }
+            }
+            Message::ApplyAllFixes => {
+                if let AppState::Doctor(Ok(diagnoses)) = &self.state {
+                    if let Err(e) = apply_safe_fixes(diagnoses) {
+                        println!("Error applying fixes: {e}");
+                    }
+                    let _ = self.refresh_mods();
+                    self.check_rules();
+                    return self.update(Message::OpenDoctor);
+                }
+            }
+            Message::RestoreMod(i) => match self.trash.restore(i) {
+                Ok(_) => {
+                    let _ = self.trash.save();
+                    let _ = self.refresh_mods();
+                    self.check_rules();
+                }
+                Err(e) => println!("Error restoring mod: {e}"),
+            },
+            Message::PurgeMod(i) => self.state = AppState::PurgeCheck(i),
+            Message::ConfirmPurge(i) => {
+                if let Err(e) = self.trash.purge(i) {
+                    println!("Error purging mod: {e}");
+                }
+                let _ = self.trash.save();
+                self.state = AppState::Trash;
+            }
+            Message::AddAllInferredRules => {
+                if let AppState::ModDetails {
+                    index,
+                    dependencies,
+                } = &self.state
+                {
+                    if let Some(m) = self.mod_list.get(*index) {
+                        for other_id in dependencies.iter().filter_map(|d| d.mod_id.clone()) {
+                            self.rules.add(ModRule {
+                                mod_id: m.key(),
+                                kind: RuleKind::Requires,
+                                other_id,
+                            });
+                        }
+                        let _ = self.rules.save();
+                        self.check_rules();
+                    }
+                }
+            }
+            Message::ConfirmPendingAction => {
+                if let AppState::RuleCheck(action) = self.state {
+                    self.run_action(action);
+                    self.state = AppState::ModList;
+                }
+            }
             // Navigation stuff
             Message::OpenConfig => self.state = AppState::Config(self.config.clone()),
             Message::ReturnToModList => {
                 self.state = AppState::ModList;
+                self.log_tail = None;
                 self.current_theme = Some(self.config.theme);
             }
             Message::OpenAbout => self.state = AppState::About,
             // Config stuff
             Message::SaveConfig => {
                 if let AppState::Config(temp_config) = &self.state {
+                    if temp_config.current_instance != self.config.current_instance {
+                        self.profile_manager =
+                            ModProfileManager::load_or_default(temp_config.current_instance);
+                        self.history = ScanHistory::load_or_default(temp_config.current_instance);
+                    }
+                    let strategy_changed =
+                        temp_config.disable_strategy != self.config.disable_strategy;
                     self.config = temp_config.clone();
                     let _ = self.config.save();
+                    let _ = self.refresh_mods();
+                    if strategy_changed {
+                        // Disabled mods are switched over to the new strategy
+                        for m in self.mod_list.iter_mut().filter(|m| !m.enabled()) {
+                            if let Err(e) = m.set_enabled(false) {
+                                println!("Error disabling mod: {e}");
+                            }
+                        }
+                    }
+                    self.check_rules();
                 }
             }
             Message::SelectGamePath => {
                 if let AppState::Config(temp_config) = &mut self.state {
                     if let Some(folder) = FileDialog::new().pick_folder() {
-                        temp_config.mods_path = folder;
+                        *temp_config.get_mods_path_mut() = folder;
+                    }
+                }
+            }
+            Message::SelectConfigInstance(instance) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.current_instance = instance.id;
+                }
+            }
+            Message::EditInstanceName(name) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    let current = temp_config.current_instance;
+                    if let Some(instance) =
+                        temp_config.instances.iter_mut().find(|i| i.id == current)
+                    {
+                        instance.name = name;
+                    }
+                }
+            }
+            Message::AddInstance => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    let name = format!("Instance {}", temp_config.instances.len() + 1);
+                    temp_config.add_instance(name);
+                }
+            }
+            Message::RemoveInstance => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.remove_current_instance();
+                }
+            }
+            Message::DetectGamePath => {
+                if let AppState::Config(temp_config) = &self.state {
+                    self.detected_installs = detect_installs(&temp_config.steam_roots);
+                }
+            }
+            Message::UseDetectedPath(i) => {
+                if let (AppState::Config(temp_config), Some(install)) =
+                    (&mut self.state, self.detected_installs.get(i))
+                {
+                    *temp_config.get_mods_path_mut() = install.mods_path.clone();
+                }
+            }
+            Message::AddSteamRoot => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    if let Some(folder) = FileDialog::new().pick_folder() {
+                        temp_config.steam_roots.push(folder);
+                        self.detected_installs = detect_installs(&temp_config.steam_roots);
+                    }
+                }
+            }
+            Message::RemoveSteamRoot(i) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    if i < temp_config.steam_roots.len() {
+                        temp_config.steam_roots.remove(i);
                     }
                 }
             }
@@ -209,39 +1154,158 @@ impl Sandbox for ModManager {
                 }
                 self.current_theme = Some(theme);
             }
-            // Misc
-            Message::ActionOpen(action) => {
-                let _ = open::that_detached(action);
+            Message::SelectLogPath => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    if let Some(file) = FileDialog::new().add_filter("Log", &["txt"]).pick_file() {
+                        temp_config.log_path = file;
+                    }
+                }
             }
-        };
-    }
-
-    fn view(&self) -> Element<'_, Message> {
-        match &self.state {
-            AppState::ModList => {
-                let mod_list = column(
-                    self.mod_list
-                        .iter()
-                        .enumerate()
-                        .map(|(i, m)| {
-                            checkbox(m.metadata.name.to_owned(), m.enabled(), move |b| {
-                                Message::Toggle(i, b)
-                            })
-                            .into()
+            Message::EditQuarantineThreshold(threshold) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    if threshold.is_empty() {
+                        temp_config.quarantine_threshold = 0;
+                    } else if let Ok(threshold) = threshold.parse() {
+                        temp_config.quarantine_threshold = threshold;
+                    }
+                }
+            }
+            Message::SwitchApiPin(b) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.always_enable_api_mods = b;
+                }
+            }
+            Message::SwitchArchiveMods(b) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.archive_mods = b;
+                }
+            }
+            Message::SwitchDisableStrategy(strategy) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.disable_strategy = strategy;
+                }
+            }
+            Message::EditExportIgnore(patterns) => {
+                if let AppState::Config(temp_config) = &mut self.state {
+                    temp_config.export_ignore = patterns.split(',').map(String::from).collect();
+                }
+            }
+            // Misc
+            Message::LaunchGame => self.run_checked(PendingAction::LaunchGame),
+            Message::ActionOpen(action) => {
+                let _ = open::that_detached(action);
+            }
+            // Mod archive
+            Message::ArchiveDone(versions, errors) => {
+                self.archiving = false;
+                for e in errors {
+                    eprintln!("Error archiving mods: {e}");
+                }
+                if !versions.is_empty() {
+                    self.archive.add(versions);
+                    let _ = self.archive.save();
+                }
+            }
+        };
+        self.archive_queued()
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        match &self.state {
+            AppState::ModList => {
+                let mod_list = column(
+                    self.mod_list
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, m)| m.content.has_facet(self.facet_filter))
+                        .map(|(i, m)| {
+                            let toggle =
+                                checkbox(m.metadata.name.to_owned(), m.enabled(), move |b| {
+                                    Message::Toggle(i, b)
+                                })
+                                .width(Length::Fill);
+                            // The default font has no lock glyph, so pins are spelled out
+                            let pin_label = match self.config.pinned_state(m) {
+                                Some(true) => "ALWAYS ON",
+                                Some(false) => "NEVER ON",
+                                None => "PIN",
+                            };
+                            let pin = button(pin_label)
+                                .on_press(Message::CyclePin(i))
+                                .style(iced::theme::Button::Secondary)
+                                .width(104);
+                            let details = button("INFO")
+                                .on_press(Message::OpenModDetails(i))
+                                .style(iced::theme::Button::Secondary)
+                                .width(64);
+                            let source = match self.workshop.as_ref().map(|w| w.status(m)) {
+                                Some(WorkshopStatus::Orphaned) => "UNSUBSCRIBED",
+                                Some(WorkshopStatus::LocalOnly) => "LOCAL",
+                                _ => "",
+                            };
+                            let source = text(source).size(14);
+                            let parked = text(if m.parked() { "PARKED" } else { "" }).size(14);
+                            row![toggle, parked, source, pin, details]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
                         })
                         .collect(),
                 )
                 .spacing(10);
-                let scroll = scrollable(mod_list).width(Length::Fill);
+                let scroll = scrollable(mod_list)
+                    .width(Length::Fill)
+                    .height(Length::Fill);
+                let facet_pick = pick_list(
+                    &ContentFacet::ALL[..],
+                    Some(self.facet_filter),
+                    Message::SelectFacet,
+                );
+                let mod_column = column![facet_pick, scroll, self.rule_report_view()]
+                    .spacing(10)
+                    .width(Length::Fill);
                 let refresh = button("REFRESH").on_press(Message::Refresh).width(128);
                 let enable_all = button("ENABLE ALL").on_press(Message::EnableAll).width(128);
                 let disable_all = button("DISABLE ALL")
                     .on_press(Message::DisableAll)
                     .width(128);
-                let top_buttons = column![refresh, enable_all, disable_all]
-                    .spacing(10)
-                    .height(Length::Fill);
+                let manage_rules = button("MOD RULES")
+                    .on_press(Message::ManageRules)
+                    .width(128);
+                let conflicts = button("CONFLICTS")
+                    .on_press(Message::OpenConflicts)
+                    .width(128);
+                let changes = button("CHANGES").on_press(Message::OpenChanges).width(128);
+                let install_zip = button("INSTALL ZIP")
+                    .on_press(Message::InstallFromZip)
+                    .width(128);
+                let install_folder = button("INSTALL FOLDER")
+                    .on_press(Message::InstallFromFolder)
+                    .width(128);
+                let trash = button("TRASH").on_press(Message::OpenTrash).width(128);
+                let new_mod = button("NEW MOD").on_press(Message::OpenNewMod).width(128);
+                let doctor = button("DOCTOR").on_press(Message::OpenDoctor).width(128);
+                let top_buttons = column![
+                    refresh,
+                    enable_all,
+                    disable_all,
+                    manage_rules,
+                    conflicts,
+                    changes,
+                    install_zip,
+                    install_folder,
+                    new_mod,
+                    trash,
+                    doctor
+                ]
+                .spacing(10)
+                .height(Length::Fill);
 
+                let instance_combo = pick_list(
+                    self.config.get_instance_choices(),
+                    self.config.get_current_instance_choice(),
+                    Message::SwitchInstance,
+                );
                 let profile_combo = pick_list(
                     &self.profile_manager.profile_states[..],
                     self.profile_manager.current_profile_state.clone(),
@@ -256,17 +1320,32 @@ impl Sandbox for ModManager {
                 let profile_new = button("MANAGE PROFILES")
                     .on_press(Message::ManageProfiles)
                     .width(128);
-                let profile_buttons =
-                    column![profile_combo, profile_new, profile_load, profile_save]
-                        .spacing(10)
-                        .height(Length::Fill);
+                let profile_buttons = column![
+                    instance_combo,
+                    profile_combo,
+                    profile_new,
+                    profile_load,
+                    profile_save
+                ]
+                .spacing(10)
+                .height(Length::Fill);
 
                 let settings_button = button("SETTINGS").on_press(Message::OpenConfig).width(128);
                 let about_button = button("ABOUT").on_press(Message::OpenAbout).width(128);
-                let bottom_buttons = column![settings_button, about_button].spacing(10);
+                let game_log_button = button("GAME LOG").on_press(Message::OpenGameLog).width(128);
+                let launch_button = button("LAUNCH GAME")
+                    .on_press(Message::LaunchGame)
+                    .width(128);
+                let bottom_buttons = column![
+                    launch_button,
+                    game_log_button,
+                    settings_button,
+                    about_button
+                ]
+                .spacing(10);
                 container(
                     row![
-                        scroll,
+                        mod_column,
                         column![top_buttons, profile_buttons, bottom_buttons],
                     ]
                     .spacing(10),
@@ -289,6 +1368,16 @@ impl Sandbox for ModManager {
                 );
                 let profiles_row = row![profiles_label, profiles_list].spacing(10);
 
+                let parent_label = text("Parent Profile")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let parent_list = pick_list(
+                    &self.profile_manager.parent_profile_states[..],
+                    self.profile_manager.current_parent_state.clone(),
+                    Message::SelectParentProfile,
+                );
+                let parent_row = row![parent_label, parent_list].spacing(10);
+
                 let input_label = text("New Profile Name")
                     .vertical_alignment(Vertical::Center)
                     .line_height(iced::widget::text::LineHeight::Relative(2.));
@@ -305,8 +1394,13 @@ impl Sandbox for ModManager {
                 let profile_buttons_row =
                     row![create_profile_button, remove_profile_button].spacing(10);
 
-                let options =
-                    column![profiles_row, profile_create_row, profile_buttons_row].spacing(10);
+                let options = column![
+                    profiles_row,
+                    parent_row,
+                    profile_create_row,
+                    profile_buttons_row
+                ]
+                .spacing(10);
 
                 let back_button = button("RETURN")
                     .on_press(Message::ReturnToModList)
@@ -321,6 +1415,1005 @@ impl Sandbox for ModManager {
                 .padding(30)
                 .into()
             }
+            AppState::Rules {
+                rule_mod,
+                rule_kind,
+                rule_other,
+            } => {
+                let header_title = text("Mod Rules")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+
+                let rule_list = column(
+                    self.rules
+                        .rules
+                        .iter()
+                        .enumerate()
+                        .map(|(i, r)| {
+                            let description = text(format!(
+                                "{} {} {}",
+                                self.get_mod_name(&r.mod_id),
+                                r.kind,
+                                self.get_mod_name(&r.other_id)
+                            ))
+                            .width(Length::Fill);
+                            let remove =
+                                button("REMOVE").on_press(Message::RemoveRule(i)).width(100);
+                            row![description, remove]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(10);
+                let scroll = scrollable(rule_list).height(Length::Fill);
+
+                let choices = self.get_mod_choices();
+                let mod_pick = pick_list(choices.clone(), rule_mod.clone(), Message::SelectRuleMod)
+                    .placeholder("Mod");
+                let kind_pick = pick_list(
+                    &RuleKind::ALL[..],
+                    Some(*rule_kind),
+                    Message::SelectRuleKind,
+                );
+                let other_pick = pick_list(choices, rule_other.clone(), Message::SelectRuleOther)
+                    .placeholder("Other mod");
+                let add_button = button("ADD RULE").on_press(Message::AddRule).width(120);
+                let new_rule_row = row![mod_pick, kind_pick, other_pick, add_button]
+                    .spacing(10)
+                    .align_items(Alignment::Center);
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![header_title, scroll, new_rule_row, back_button]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::NewMod { new_mod, error } => {
+                let header_title = text("New Mod")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let field = |label: &str, input: Element<'static, Message>| {
+                    let label = text(label)
+                        .width(160)
+                        .vertical_alignment(Vertical::Center)
+                        .line_height(iced::widget::text::LineHeight::Relative(2.));
+                    row![label, input].spacing(10)
+                };
+                let name = text_input("Name shown in the mods menu", &new_mod.name)
+                    .on_input(Message::EditNewModName);
+                let directory = text_input("Folder name", &new_mod.directory)
+                    .on_input(Message::EditNewModDirectory);
+                let description = text_input("Description", &new_mod.description)
+                    .on_input(Message::EditNewModDescription);
+                let version =
+                    text_input("Version", &new_mod.version).on_input(Message::EditNewModVersion);
+                let visibility = pick_list(
+                    &VISIBILITIES[..],
+                    Some(new_mod.visibility),
+                    Message::SelectNewModVisibility,
+                );
+                let tags = tag_grid(&new_mod.tags, Message::ToggleNewModTag);
+                let with_content = checkbox(
+                    "Add a content folder with an empty items.xml",
+                    new_mod.with_content,
+                    Message::SwitchNewModContent,
+                );
+                let with_resources = checkbox(
+                    "Add a resources folder for graphics and sounds",
+                    new_mod.with_resources,
+                    Message::SwitchNewModResources,
+                );
+                let mut form = column![
+                    field("Name", name.into()),
+                    field("Directory", directory.into()),
+                    field("Description", description.into()),
+                    field("Version", version.into()),
+                    field("Visibility", visibility.into()),
+                    text("Tags").size(20),
+                    tags,
+                    with_content,
+                    with_resources,
+                ]
+                .spacing(10);
+                if let Some(error) = error {
+                    form = form.push(
+                        text(format!("Cannot create the mod: {error}"))
+                            .style(Color::from_rgb(0.8, 0.2, 0.2)),
+                    );
+                }
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(200);
+                let create_button = button("CREATE").on_press(Message::CreateNewMod).width(200);
+                let end_row = row![back_button, create_button].spacing(20);
+                container(
+                    column![header_title, scrollable(form).height(Length::Fill), end_row]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::EditMetadata {
+                metadata, error, ..
+            } => {
+                let header_title = text("Edit Metadata")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let field = |label: &str, input: Element<'static, Message>| {
+                    let label = text(label)
+                        .width(160)
+                        .vertical_alignment(Vertical::Center)
+                        .line_height(iced::widget::text::LineHeight::Relative(2.));
+                    row![label, input].spacing(10)
+                };
+                let name = text_input("Name", &metadata.name).on_input(Message::EditMetadataName);
+                let description = text_input("Description", &metadata.description)
+                    .on_input(Message::EditMetadataDescription);
+                let version = row![
+                    text_input("Version", &metadata.version).on_input(Message::EditMetadataVersion),
+                    button("PATCH")
+                        .on_press(Message::BumpVersion(VersionPart::Patch))
+                        .width(80),
+                    button("MINOR")
+                        .on_press(Message::BumpVersion(VersionPart::Minor))
+                        .width(80),
+                    button("MAJOR")
+                        .on_press(Message::BumpVersion(VersionPart::Major))
+                        .width(80),
+                ]
+                .spacing(10);
+                let visibility = pick_list(
+                    &VISIBILITIES[..],
+                    VISIBILITIES
+                        .iter()
+                        .find(|v| v.eq_ignore_ascii_case(&metadata.visibility))
+                        .copied(),
+                    Message::SelectMetadataVisibility,
+                );
+                let selected_tags: Vec<ModTagId> =
+                    metadata.tags.iter().flatten().map(|t| t.id).collect();
+                let mut form = column![
+                    field("Name", name.into()),
+                    field("Description", description.into()),
+                    field("Version", version.into()),
+                    field("Visibility", visibility.into()),
+                    text("Tags").size(20),
+                    tag_grid(&selected_tags, Message::ToggleMetadataTag),
+                ]
+                .spacing(10);
+                if let Some(error) = error {
+                    form = form.push(
+                        text(format!("Cannot save the metadata: {error}"))
+                            .style(Color::from_rgb(0.9, 0.2, 0.2)),
+                    );
+                }
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(200);
+                let save_button = button("SAVE").on_press(Message::SaveMetadata).width(200);
+                let end_row = row![back_button, save_button].spacing(20);
+                container(
+                    column![header_title, scrollable(form).height(Length::Fill), end_row]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Trash => {
+                let header_title = text("Uninstalled Mods")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let entry_list = column(
+                    self.trash
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| {
+                            let days = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map_or(0, |d| d.as_secs().saturating_sub(e.time))
+                                / 86400;
+                            let profiles = if e.profiles.is_empty() {
+                                "Not enabled by any profile".to_string()
+                            } else {
+                                format!("Enabled by {}", e.profiles.join(", "))
+                            };
+                            let description = column![
+                                text(format!("{} (version {})", e.name, e.version)),
+                                text(format!(
+                                    "Uninstalled {days} days ago from {}",
+                                    e.original_path.display()
+                                ))
+                                .size(14),
+                                text(profiles).size(14),
+                            ]
+                            .width(Length::Fill);
+                            let restore = button("RESTORE")
+                                .on_press(Message::RestoreMod(i))
+                                .width(100);
+                            let purge = button("PURGE")
+                                .on_press(Message::PurgeMod(i))
+                                .style(iced::theme::Button::Destructive)
+                                .width(100);
+                            row![description, restore, purge]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(10);
+                let content: Element<'_, Message> = if self.trash.entries.is_empty() {
+                    text("The trash is empty.").into()
+                } else {
+                    scrollable(entry_list.width(Length::Fill)).into()
+                };
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        back_button
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Doctor(diagnoses) => {
+                let header_title = text("Mods Folder Health")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let mut fix_all_button = button("FIX ALL SAFE").width(160);
+                let content: Element<'_, Message> = match diagnoses {
+                    Ok(diagnoses) if diagnoses.is_empty() => {
+                        text("No problems found in the mods folder.").into()
+                    }
+                    Ok(diagnoses) => {
+                        if diagnoses.iter().any(|d| d.fix.is_some()) {
+                            fix_all_button = fix_all_button.on_press(Message::ApplyAllFixes);
+                        }
+                        let diagnosis_list = column(
+                            diagnoses
+                                .iter()
+                                .enumerate()
+                                .map(|(i, d)| {
+                                    let mut severity = text(d.severity.to_string()).width(100);
+                                    if d.severity == Severity::Error {
+                                        severity = severity.style(Color::from_rgb(0.9, 0.2, 0.2));
+                                    }
+                                    let description = column![
+                                        text(format!("{}: {}", d.folder, d.message)),
+                                        text(format!("Fix: {}", d.suggestion)).size(14),
+                                    ]
+                                    .width(Length::Fill);
+                                    let mut fix_button = button("FIX").width(100);
+                                    if d.fix.is_some() {
+                                        fix_button = fix_button.on_press(Message::ApplyFix(i));
+                                    }
+                                    row![severity, description, fix_button]
+                                        .spacing(10)
+                                        .align_items(Alignment::Center)
+                                        .into()
+                                })
+                                .collect(),
+                        )
+                        .spacing(10);
+                        scrollable(diagnosis_list.width(Length::Fill)).into()
+                    }
+                    Err(e) => text(format!("Cannot scan the mods folder: {e}"))
+                        .style(Color::from_rgb(0.9, 0.2, 0.2))
+                        .into(),
+                };
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        row![back_button, fix_all_button].spacing(20)
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Install(plan) => {
+                let header_title = text("Install Mod")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let mut confirm_button = button("INSTALL").width(200);
+                let summary = match plan {
+                    Ok(plan) => {
+                        confirm_button = confirm_button.on_press(Message::ConfirmInstall);
+                        column![
+                            text(format!("{} (version {})", plan.name, plan.version)).size(24),
+                            text(format!("ID: {}", plan.id)),
+                            text(format!("From: {}", plan.source.display())),
+                            text(format!(
+                                "To: {}",
+                                self.config.get_mods_path().join(&plan.folder).display()
+                            )),
+                        ]
+                        .spacing(5)
+                    }
+                    Err(e) => column![text(format!("This mod cannot be installed: {e}"))],
+                };
+
+                let back_button = button("CANCEL").on_press(Message::CancelInstall).width(200);
+                let end_row = row![back_button, confirm_button].spacing(20);
+                container(
+                    column![header_title, summary.height(Length::Fill), end_row]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::PurgeCheck(i) => {
+                let header_title = text("Purge Mod")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let description = match self.trash.entries.get(*i) {
+                    Some(e) => format!(
+                        "{} (version {}) will be deleted for good.",
+                        e.name, e.version
+                    ),
+                    None => "This mod is no longer in the trash.".to_string(),
+                };
+
+                let back_button = button("CANCEL").on_press(Message::OpenTrash).width(200);
+                let mut confirm_button = button("PURGE")
+                    .style(iced::theme::Button::Destructive)
+                    .width(200);
+                if self.trash.entries.get(*i).is_some() {
+                    confirm_button = confirm_button.on_press(Message::ConfirmPurge(*i));
+                }
+                let end_row = row![back_button, confirm_button].spacing(20);
+                container(
+                    column![
+                        header_title,
+                        container(text(description)).height(Length::Fill),
+                        end_row
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::RuleCheck(action) => {
+                let header_title = text("Broken Mod Rules")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let action_name = match action {
+                    PendingAction::SaveProfile => "SAVE PROFILE ANYWAY",
+                    PendingAction::LaunchGame => "LAUNCH ANYWAY",
+                };
+                let report = if self.rule_report.is_empty() {
+                    column![text("Every rule is now satisfied.")]
+                } else {
+                    self.rule_report_view()
+                };
+
+                let back_button = button("CANCEL")
+                    .on_press(Message::ReturnToModList)
+                    .width(200);
+                let confirm_button = button(action_name)
+                    .on_press(Message::ConfirmPendingAction)
+                    .width(200);
+                let end_row = row![back_button, confirm_button].spacing(20);
+                container(
+                    column![header_title, report.height(Length::Fill), end_row]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::ModDetails {
+                index,
+                dependencies,
+            } => {
+                let Some(m) = self.mod_list.get(*index) else {
+                    return text("This mod no longer exists.").into();
+                };
+                let header_title = text(&m.metadata.name)
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let tags = m
+                    .metadata
+                    .tags
+                    .iter()
+                    .flatten()
+                    .map(|t| format!("{:?}", t.id))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let info = column![
+                    text(format!("ID: {}", m.metadata.id)),
+                    text(format!("Version: {}", m.metadata.version)),
+                    text(format!("Directory: {}", m.metadata.directory)),
+                    text(format!("Visibility: {}", m.metadata.visibility)),
+                    text(format!("Tags: {tags}")),
+                    text(format!("Location: {}", m.path.display())),
+                    text(&m.metadata.description).size(14),
+                ]
+                .spacing(5);
+                let workshop_info = match &self.workshop {
+                    Some(workshop) => match (workshop.status(m), workshop.get(m.metadata.id)) {
+                        (WorkshopStatus::Subscribed, Some(item)) => {
+                            let days = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map_or(0, |d| d.as_secs().saturating_sub(item.time_updated))
+                                / 86400;
+                            format!(
+                                "Workshop: subscribed, {:.1} MB, updated {days} days ago",
+                                item.size as f64 / 1_048_576.
+                            )
+                        }
+                        (WorkshopStatus::Orphaned, _) => {
+                            "Workshop: no longer subscribed, Steam may delete this folder"
+                                .to_string()
+                        }
+                        _ => "Workshop: local mod, not downloaded from the workshop".to_string(),
+                    },
+                    None => "Workshop: Steam workshop manifest not found".to_string(),
+                };
+                let info = info.push(text(workshop_info).size(14));
+                let content_description = m.content.describe();
+                let content_counts = m
+                    .content
+                    .counts()
+                    .iter()
+                    .map(|(file, count)| format!("{count} in {file}"))
+                    .collect::<Vec<_>>();
+                let content_info = column![
+                    text(if content_description.is_empty() {
+                        "Adds nothing the manager recognizes".to_string()
+                    } else {
+                        format!("Adds: {}", content_description.join(", "))
+                    }),
+                    text(if content_counts.is_empty() {
+                        "No content entries".to_string()
+                    } else {
+                        format!("Content entries: {}", content_counts.join(", "))
+                    })
+                    .size(14),
+                ]
+                .spacing(5);
+
+                let dependency_list = column(
+                    dependencies
+                        .iter()
+                        .map(|d| {
+                            let status = match d.mod_id {
+                                Some(_) => "",
+                                None => " (not installed)",
+                            };
+                            let description = text(format!("{}{status}: {}", d.name, d.reason))
+                                .width(Length::Fill);
+                            let has_rule = d.mod_id.as_ref().is_some_and(|key| {
+                                self.rules.rules.contains(&ModRule {
+                                    mod_id: m.key(),
+                                    kind: RuleKind::Requires,
+                                    other_id: key.clone(),
+                                })
+                            });
+                            let mut add_rule = button("ADD RULE").width(100);
+                            if let (Some(key), false) = (&d.mod_id, has_rule) {
+                                add_rule = add_rule.on_press(Message::AddInferredRule(key.clone()));
+                            }
+                            row![description, add_rule]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(10);
+                let dependencies_title = text(if dependencies.is_empty() {
+                    "No dependencies found in Lua code"
+                } else {
+                    "Dependencies found in Lua code"
+                })
+                .size(20);
+                let version_list = column(
+                    self.archive
+                        .versions_of(&m.key())
+                        .map(|(i, v)| {
+                            let days = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map_or(0, |d| d.as_secs().saturating_sub(v.time))
+                                / 86400;
+                            let description =
+                                text(format!("Version {}, archived {days} days ago", v.version))
+                                    .width(Length::Fill);
+                            let rollback = button("ROLL BACK")
+                                .on_press(Message::RollbackMod(i))
+                                .width(100);
+                            row![description, rollback]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(10);
+                let versions_title = text(if self.archive.versions_of(&m.key()).count() == 0 {
+                    "No archived versions"
+                } else {
+                    "Archived versions"
+                })
+                .size(20);
+                let details = scrollable(
+                    column![
+                        info,
+                        content_info,
+                        dependencies_title,
+                        dependency_list,
+                        versions_title,
+                        version_list
+                    ]
+                    .spacing(20),
+                )
+                .height(Length::Fill);
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(200);
+                let add_all_button = button("ADD ALL AS RULES")
+                    .on_press(Message::AddAllInferredRules)
+                    .width(200);
+                let uninstall_button = button("UNINSTALL")
+                    .on_press(Message::UninstallMod)
+                    .style(iced::theme::Button::Destructive)
+                    .width(160);
+                let export_button = button("EXPORT").on_press(Message::ExportMod).width(160);
+                let validate_button = button("VALIDATE").on_press(Message::ValidateMod).width(160);
+                // Steam overwrites workshop mods on update, only local ones are worth editing
+                let mut edit_button = button("EDIT").width(160);
+                let status = match &self.workshop {
+                    Some(workshop) => workshop.status(m),
+                    None => Workshop::default().status(m),
+                };
+                if status == WorkshopStatus::LocalOnly {
+                    edit_button = edit_button.on_press(Message::EditMetadata);
+                }
+                let end_row = row![
+                    back_button,
+                    add_all_button,
+                    edit_button,
+                    validate_button,
+                    export_button,
+                    uninstall_button
+                ]
+                .spacing(20);
+                container(
+                    column![header_title, details, end_row]
+                        .spacing(30)
+                        .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Conflicts {
+                overrides,
+                collisions,
+            } => {
+                const SHOWN_PATHS: usize = 20;
+                let header_title = text("Mod Conflicts")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let override_list = column(
+                    overrides
+                        .iter()
+                        .map(|c| {
+                            let title = text(format!(
+                                "{} and {} ({} files)",
+                                self.get_mod_name(&c.mods.0),
+                                self.get_mod_name(&c.mods.1),
+                                c.paths.len()
+                            ))
+                            .size(20);
+                            let mut paths = column(
+                                c.paths
+                                    .iter()
+                                    .take(SHOWN_PATHS)
+                                    .map(|p| text(p).size(14).into())
+                                    .collect(),
+                            );
+                            if c.paths.len() > SHOWN_PATHS {
+                                paths = paths.push(
+                                    text(format!("... and {} more", c.paths.len() - SHOWN_PATHS))
+                                        .size(14),
+                                );
+                            }
+                            column![title, paths].spacing(5).into()
+                        })
+                        .collect(),
+                )
+                .spacing(20);
+                let collision_list = column(
+                    collisions
+                        .iter()
+                        .map(|c| {
+                            text(format!(
+                                "{} and {} both add {} in {}",
+                                self.get_mod_name(&c.mods.0),
+                                self.get_mod_name(&c.mods.1),
+                                c.description,
+                                c.file
+                            ))
+                            .into()
+                        })
+                        .collect(),
+                )
+                .spacing(5);
+                let content: Element<'_, Message> = if overrides.is_empty() && collisions.is_empty()
+                {
+                    text("No enabled mods conflict with each other.").into()
+                } else {
+                    scrollable(
+                        column![
+                            text("Resource overrides").size(24),
+                            override_list,
+                            text("Content collisions").size(24),
+                            collision_list
+                        ]
+                        .spacing(20)
+                        .width(Length::Fill),
+                    )
+                    .into()
+                };
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        back_button
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Changes => {
+                let header_title = text("Changes Since Last Scan")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let content: Element<'_, Message> = match self.change_report.since {
+                    Some(since) if !self.change_report.changes.is_empty() => {
+                        let days = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |d| d.as_secs().saturating_sub(since))
+                            / 86400;
+                        let change_list = column(
+                            self.change_report
+                                .changes
+                                .iter()
+                                .map(|c| text(c.to_string()).into())
+                                .collect(),
+                        )
+                        .spacing(5);
+                        scrollable(
+                            column![
+                                text(format!("Compared to the scan from {days} days ago")).size(14),
+                                change_list
+                            ]
+                            .spacing(20)
+                            .width(Length::Fill),
+                        )
+                        .into()
+                    }
+                    _ => text("No mods changed since the last scan.").into(),
+                };
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        back_button
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::Validation { index, report } => {
+                let name = self
+                    .mod_list
+                    .get(*index)
+                    .map_or("", |m| m.metadata.name.as_str());
+                let header_title = text(format!("Upload Check: {name}"))
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let (verdict, color) = if report.passed() {
+                    ("PASS: ready to upload", Color::from_rgb(0.2, 0.7, 0.3))
+                } else {
+                    (
+                        "FAIL: fix the errors before uploading",
+                        Color::from_rgb(0.9, 0.2, 0.2),
+                    )
+                };
+                let issue_list = column(
+                    report
+                        .issues
+                        .iter()
+                        .map(|i| {
+                            let mut severity = text(i.severity.to_string()).width(100);
+                            if i.severity == Severity::Error {
+                                severity = severity.style(Color::from_rgb(0.9, 0.2, 0.2));
+                            }
+                            row![severity, text(&i.message).width(Length::Fill)]
+                                .spacing(10)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(5);
+                let content = scrollable(
+                    column![text(verdict).size(24).style(color), issue_list]
+                        .spacing(20)
+                        .width(Length::Fill),
+                );
+
+                let back_button = button("RETURN")
+                    .on_press(Message::OpenModDetails(*index))
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        back_button
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::GameLog(report) => {
+                let header_title = text("Game Log Errors")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let mut sections = column![].spacing(20).width(Length::Fill);
+                match report {
+                    Ok(report) => {
+                        let proposed = self
+                            .quarantine
+                            .propose(report, self.config.quarantine_threshold);
+                        if !proposed.is_empty() {
+                            let proposed_list = column(
+                                proposed
+                                    .iter()
+                                    .map(|e| {
+                                        let description = text(format!(
+                                            "{} threw {} errors",
+                                            self.get_mod_name(&e.mod_id),
+                                            e.error_count
+                                        ))
+                                        .width(Length::Fill);
+                                        let quarantine_button = button("QUARANTINE")
+                                            .on_press(Message::QuarantineMod(e.mod_id.clone()))
+                                            .width(120);
+                                        row![description, quarantine_button]
+                                            .spacing(10)
+                                            .align_items(Alignment::Center)
+                                            .into()
+                                    })
+                                    .collect(),
+                            )
+                            .spacing(10);
+                            let quarantine_all = button("QUARANTINE ALL")
+                                .on_press(Message::QuarantineAll)
+                                .width(160);
+                            sections = sections.push(
+                                column![
+                                    text("Proposed for quarantine").size(24),
+                                    proposed_list,
+                                    quarantine_all
+                                ]
+                                .spacing(10),
+                            );
+                        }
+
+                        let mut error_list = column(
+                            report
+                                .mods
+                                .iter()
+                                .map(|errors| {
+                                    let title = text(format!(
+                                        "{} ({} errors)",
+                                        self.get_mod_name(&errors.mod_id),
+                                        errors.count
+                                    ))
+                                    .size(20);
+                                    let messages = column(
+                                        errors
+                                            .latest
+                                            .iter()
+                                            .map(|m| text(m).size(14).into())
+                                            .collect(),
+                                    );
+                                    column![title, messages].spacing(5).into()
+                                })
+                                .collect(),
+                        )
+                        .spacing(20);
+                        if report.mods.is_empty() {
+                            error_list = error_list
+                                .push(text("No errors attributed to mods in the game log."));
+                        }
+                        if report.unattributed > 0 {
+                            error_list = error_list.push(text(format!(
+                                "{} errors could not be attributed to a mod",
+                                report.unattributed
+                            )));
+                        }
+                        sections = sections.push(error_list);
+                    }
+                    Err(e) => {
+                        sections = sections.push(text(format!("Cannot read the game log: {e}")))
+                    }
+                };
+                if !self.quarantine.entries.is_empty() {
+                    let quarantine_list = column(
+                        self.quarantine
+                            .entries
+                            .iter()
+                            .map(|e| {
+                                let description = column![
+                                    text(format!(
+                                        "{} ({} errors)",
+                                        self.get_mod_name(&e.mod_id),
+                                        e.error_count
+                                    )),
+                                    text(&e.error).size(14)
+                                ]
+                                .width(Length::Fill);
+                                let release_button = button("RELEASE")
+                                    .on_press(Message::ReleaseMod(e.mod_id.clone()))
+                                    .width(120);
+                                row![description, release_button]
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .into()
+                            })
+                            .collect(),
+                    )
+                    .spacing(10);
+                    sections = sections.push(
+                        column![text("Quarantined mods").size(24), quarantine_list].spacing(10),
+                    );
+                }
+                let content = scrollable(sections);
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                let reload_button = button("RELOAD").on_press(Message::OpenGameLog).width(120);
+                let live_button = button("LIVE VIEW")
+                    .on_press(Message::OpenLiveLog)
+                    .width(120);
+                let end_row = row![back_button, reload_button, live_button].spacing(20);
+                container(
+                    column![
+                        header_title,
+                        container(content).height(Length::Fill),
+                        end_row
+                    ]
+                    .spacing(30)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
+            AppState::LiveLog { filter, error } => {
+                const SHOWN_LINES: usize = 500;
+                let header_title = text("Live Game Log")
+                    .size(32)
+                    .horizontal_alignment(Horizontal::Center);
+                let filter_pick =
+                    pick_list(self.get_mod_choices(), filter.clone(), Message::FilterLog)
+                        .placeholder("All mods");
+                let clear_button = button("SHOW ALL")
+                    .on_press(Message::ClearLogFilter)
+                    .width(120);
+                let filter_row = row![filter_pick, clear_button]
+                    .spacing(10)
+                    .align_items(Alignment::Center);
+
+                let lines: Vec<_> = self
+                    .log_tail
+                    .iter()
+                    .flat_map(|t| t.lines.iter())
+                    .filter(|l| {
+                        filter
+                            .as_ref()
+                            .map_or(true, |f| l.mod_id.as_ref() == Some(&f.key))
+                    })
+                    .collect();
+                let log_lines = column(
+                    lines
+                        .iter()
+                        .skip(lines.len().saturating_sub(SHOWN_LINES))
+                        .map(|l| {
+                            let color = match l.level {
+                                LineLevel::Error => Some(Color::from_rgb(0.9, 0.2, 0.2)),
+                                LineLevel::Warning => Some(Color::from_rgb(0.9, 0.6, 0.1)),
+                                LineLevel::Info => None,
+                            };
+                            let mut line = text(&l.text).size(14).width(Length::Fill);
+                            if let Some(color) = color {
+                                line = line.style(color);
+                            }
+                            match &l.mod_id {
+                                Some(key) => row![
+                                    line,
+                                    button("MOD")
+                                        .on_press(Message::OpenModDetailsByKey(key.clone()))
+                                        .style(iced::theme::Button::Secondary)
+                                        .width(64)
+                                ]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into(),
+                                None => line.into(),
+                            }
+                        })
+                        .collect(),
+                )
+                .spacing(2)
+                .width(Length::Fill);
+                let content: Element<'_, Message> = match error {
+                    Some(e) => text(format!("Cannot read the game log: {e}")).into(),
+                    None => scrollable(log_lines)
+                        .id(scrollable::Id::new(LOG_SCROLLABLE))
+                        .into(),
+                };
+
+                let back_button = button("RETURN")
+                    .on_press(Message::ReturnToModList)
+                    .width(120);
+                container(
+                    column![
+                        header_title,
+                        filter_row,
+                        container(content).height(Length::Fill),
+                        back_button
+                    ]
+                    .spacing(20)
+                    .align_items(Alignment::Center),
+                )
+                .padding(30)
+                .into()
+            }
             AppState::Config(temp_config) => {
                 let back_button = button("RETURN")
                     .on_press(Message::ReturnToModList)
@@ -330,23 +2423,170 @@ impl Sandbox for ModManager {
                     .horizontal_alignment(Horizontal::Center)
                     .size(32);
                 let end_row = row![back_button, save_button].spacing(10);
+                let instance_label = text("Game instance")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let instance_combo = pick_list(
+                    temp_config.get_instance_choices(),
+                    temp_config.get_current_instance_choice(),
+                    Message::SelectConfigInstance,
+                );
+                let current_instance = temp_config
+                    .instances
+                    .iter()
+                    .find(|i| i.id == temp_config.current_instance);
+                let mut instance_name = text_input(
+                    "Instance name",
+                    current_instance.map_or("<default>", |i| i.name.as_str()),
+                );
+                let mut instance_remove = button("REMOVE")
+                    .style(iced::theme::Button::Secondary)
+                    .width(120);
+                if current_instance.is_some() {
+                    instance_name = instance_name.on_input(Message::EditInstanceName);
+                    instance_remove = instance_remove.on_press(Message::RemoveInstance);
+                }
+                let instance_add = button("ADD").on_press(Message::AddInstance).width(120);
+                let instance = row![
+                    instance_label,
+                    instance_combo,
+                    instance_name,
+                    instance_add,
+                    instance_remove
+                ]
+                .spacing(10);
                 let game_path_label = text("Path to game mods")
                     .vertical_alignment(Vertical::Center)
                     .line_height(iced::widget::text::LineHeight::Relative(2.));
-                let game_path_field =
-                    text_input("Path to game mods", temp_config.mods_path.to_str().unwrap());
+                let game_path_field = text_input(
+                    "Path to game mods",
+                    temp_config.get_mods_path().to_str().unwrap(),
+                );
                 let game_path_button = button("BROWSE")
                     .on_press(Message::SelectGamePath)
                     .width(120);
-                let game_path =
-                    row![game_path_label, game_path_field, game_path_button].spacing(10);
+                let game_path_detect = button("DETECT")
+                    .on_press(Message::DetectGamePath)
+                    .width(120);
+                let game_path = row![
+                    game_path_label,
+                    game_path_field,
+                    game_path_button,
+                    game_path_detect
+                ]
+                .spacing(10);
+                let detected_paths = column(
+                    self.detected_installs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, install)| {
+                            let path =
+                                text(install.mods_path.display().to_string()).width(Length::Fill);
+                            let use_button = button("USE")
+                                .on_press(Message::UseDetectedPath(i))
+                                .style(iced::theme::Button::Secondary)
+                                .width(120);
+                            row![path, use_button]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(5);
+                let steam_roots_label = text("Extra Steam folders")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let steam_roots_button = button("ADD").on_press(Message::AddSteamRoot).width(120);
+                let steam_roots_list = column(
+                    temp_config
+                        .steam_roots
+                        .iter()
+                        .enumerate()
+                        .map(|(i, root)| {
+                            let path = text(root.display().to_string()).width(Length::Fill);
+                            let remove_button = button("REMOVE")
+                                .on_press(Message::RemoveSteamRoot(i))
+                                .style(iced::theme::Button::Secondary)
+                                .width(120);
+                            row![path, remove_button]
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(5);
+                let steam_roots = column![
+                    row![steam_roots_label, steam_roots_button].spacing(10),
+                    steam_roots_list
+                ]
+                .spacing(5);
+                let log_path_label = text("Path to game log")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let log_path_field = text_input(
+                    "Default game log location",
+                    &temp_config.log_path.to_string_lossy(),
+                );
+                let log_path_button = button("BROWSE").on_press(Message::SelectLogPath).width(120);
+                let log_path = row![log_path_label, log_path_field, log_path_button].spacing(10);
+                let threshold_label = text("Errors before proposing quarantine")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let threshold_field =
+                    text_input("Error count", &temp_config.quarantine_threshold.to_string())
+                        .on_input(Message::EditQuarantineThreshold);
+                let threshold = row![threshold_label, threshold_field].spacing(10);
                 let theme_label = text("Theme")
                     .vertical_alignment(Vertical::Center)
                     .line_height(iced::widget::text::LineHeight::Relative(2.));
                 let theme_pick =
                     pick_list(&Theme::ALL[..], self.current_theme, Message::SwitchTheme);
                 let theme = row![theme_label, theme_pick].spacing(10);
-                let settings_col = column![game_path, theme].spacing(10).height(Length::Fill);
+                let api_pin = checkbox(
+                    "Always enable API mods",
+                    temp_config.always_enable_api_mods,
+                    Message::SwitchApiPin,
+                );
+                let export_ignore_label = text("Left out of exports")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let export_ignore_field = text_input(
+                    "Comma separated patterns, like *.bak",
+                    &temp_config.export_ignore.join(","),
+                )
+                .on_input(Message::EditExportIgnore);
+                let export_ignore = row![export_ignore_label, export_ignore_field].spacing(10);
+                let archive_mods = checkbox(
+                    "Archive new mod versions to roll back updates",
+                    temp_config.archive_mods,
+                    Message::SwitchArchiveMods,
+                );
+                let strategy_label = text("Disable mods by")
+                    .vertical_alignment(Vertical::Center)
+                    .line_height(iced::widget::text::LineHeight::Relative(2.));
+                let strategy_pick = pick_list(
+                    &DisableStrategy::ALL[..],
+                    Some(temp_config.disable_strategy),
+                    Message::SwitchDisableStrategy,
+                );
+                let strategy = row![strategy_label, strategy_pick].spacing(10);
+                let settings_col = column![
+                    instance,
+                    game_path,
+                    detected_paths,
+                    steam_roots,
+                    log_path,
+                    threshold,
+                    theme,
+                    api_pin,
+                    strategy,
+                    archive_mods,
+                    export_ignore
+                ]
+                .spacing(10)
+                .height(Length::Fill);
                 container(
                     column![header, settings_col, end_row]
                         .spacing(20)
@@ -403,6 +2643,34 @@ impl Sandbox for ModManager {
     }
 }
 
+/// Checkboxes for every mod tag, four per row.
+fn tag_grid<'a>(
+    selected: &[ModTagId],
+    on_toggle: fn(ModTagId, bool) -> Message,
+) -> iced::widget::Column<'a, Message> {
+    column(
+        ModTagId::ALL
+            .chunks(4)
+            .map(|tags| {
+                row(tags
+                    .iter()
+                    .map(|tag| {
+                        let tag = *tag;
+                        checkbox(tag.to_string(), selected.contains(&tag), move |b| {
+                            on_toggle(tag, b)
+                        })
+                        .width(Length::FillPortion(1))
+                        .into()
+                    })
+                    .collect())
+                .spacing(10)
+                .into()
+            })
+            .collect(),
+    )
+    .spacing(5)
+}
+
 fn button(text: &str) -> iced::widget::Button<'_, Message> {
     iced::widget::button(
         iced::widget::text(text).horizontal_alignment(iced::alignment::Horizontal::Center),
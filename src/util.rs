@@ -1,4 +1,8 @@
-use std::{fs::File, io, path::PathBuf};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
 
 pub fn create_empty_file(path: PathBuf) -> io::Result<()> {
     let _ = File::create(path)?;
@@ -11,3 +15,100 @@ pub fn get_config_dir() -> Option<PathBuf> {
         .join("IcyIsaacModManager");
     Some(dir)
 }
+
+/// Whether a file name is the marker that disables a mod. The game under Proton ignores its
+/// case, so every spelling counts.
+pub fn is_disable_marker(name: &str) -> bool {
+    name.eq_ignore_ascii_case("disable.it")
+}
+
+/// Disable markers directly inside a mod folder, whatever their case.
+pub fn find_disable_markers(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|e| is_disable_marker(&e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect()
+}
+
+/// Lists every file under a directory, recursively. Unreadable entries are skipped.
+pub fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => dirs.push(path),
+                Ok(t) if t.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files
+}
+
+/// Copies a directory and everything in it to a new location.
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for file in list_files(from) {
+        let Ok(relative) = file.strip_prefix(from) else {
+            continue;
+        };
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&file, target)?;
+    }
+    Ok(())
+}
+
+/// Fixtures shared by the tests of every module.
+#[cfg(test)]
+pub mod test {
+    use std::{
+        fs,
+        ops::Deref,
+        path::{Path, PathBuf},
+    };
+
+    /// A folder for a test under the system temp folder, removed when dropped so that failing
+    /// asserts do not leave it behind.
+    pub struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("icy-isaac-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Writes a mod folder named after the mod, with the metadata.xml fields the game requires
+    /// and any extra elements such as tags. Returns the folder.
+    pub fn write_mod(mods_path: &Path, folder: &str, extra: &str) -> PathBuf {
+        let path = mods_path.join(folder);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("metadata.xml"), format!("<metadata><name>{folder}</name><directory>{folder}</directory><description/><version>1</version><visibility>Public</visibility>{extra}</metadata>")).unwrap();
+        path
+    }
+}
@@ -0,0 +1,352 @@
+use std::{fs, path::Path};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::{
+    export::is_ignored,
+    scaffold::VISIBILITIES,
+    types::ModTagId,
+    util::{find_disable_markers, is_disable_marker, list_files},
+};
+
+/// Longest description the workshop accepts.
+const MAX_DESCRIPTION: usize = 8000;
+/// Files above this size are most likely not meant to be uploaded.
+const LARGE_FILE: u64 = 20 * 1024 * 1024;
+/// BBCode tags the workshop renders that need a closing tag.
+const PAIRED_BBCODE: [&str; 17] = [
+    "b", "i", "u", "strike", "spoiler", "noparse", "h1", "h2", "h3", "url", "list", "olist",
+    "quote", "code", "table", "tr", "td",
+];
+/// BBCode tags standing on their own.
+const SINGLE_BBCODE: [&str; 3] = ["*", "hr", "img"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Warning => "WARNING",
+                Self::Error => "ERROR",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Problems found in a mod folder before uploading it to the workshop.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn passed(&self) -> bool {
+        self.issues.iter().all(|i| i.severity != Severity::Error)
+    }
+
+    fn error(&mut self, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    fn warning(&mut self, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+}
+
+/// Fields of `metadata.xml` read without validating them, so that invalid values can be reported.
+#[derive(Debug, Default)]
+struct RawMetadata {
+    name: Option<String>,
+    directory: Option<String>,
+    id: Option<String>,
+    description: Option<String>,
+    visibility: Option<String>,
+    tags: Vec<String>,
+}
+
+fn read_raw_metadata(contents: &str) -> Result<RawMetadata, quick_xml::Error> {
+    let mut reader = Reader::from_str(contents);
+    let mut raw = RawMetadata::default();
+    let mut depth = 0;
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                depth += 1;
+                if depth == 2 {
+                    current = Some(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                }
+                if depth == 2 && e.name().as_ref() == b"tag" {
+                    if let Some(id) = e.try_get_attribute("id")? {
+                        raw.tags.push(id.unescape_value()?.into_owned());
+                    }
+                }
+            }
+            Event::Empty(e) if depth == 1 && e.name().as_ref() == b"tag" => {
+                if let Some(id) = e.try_get_attribute("id")? {
+                    raw.tags.push(id.unescape_value()?.into_owned());
+                }
+            }
+            Event::Text(t) if depth == 2 => {
+                let value = t.unescape()?.into_owned();
+                match current.as_deref() {
+                    Some("name") => raw.name = Some(value),
+                    Some("directory") => raw.directory = Some(value),
+                    Some("id") => raw.id = Some(value),
+                    Some("description") => raw.description = Some(value),
+                    Some("visibility") => raw.visibility = Some(value),
+                    _ => {}
+                }
+            }
+            Event::End(_) => {
+                depth -= 1;
+                if depth < 2 {
+                    current = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(raw)
+}
+
+/// Checks that BBCode tags are properly nested, returning the first problem found. Words in
+/// brackets that are not workshop tags may just be plain text, they are returned as warnings.
+pub fn check_bbcode(text: &str) -> Result<Vec<String>, String> {
+    let mut open: Vec<String> = Vec::new();
+    let mut unknown: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        let raw = rest[..end].to_string();
+        let tag = raw.to_lowercase();
+        rest = &rest[end + 1..];
+        // Text between [noparse] tags is shown as is
+        if open.last().is_some_and(|t| t == "noparse") && tag != "/noparse" {
+            continue;
+        }
+        if let Some(closing) = tag.strip_prefix('/') {
+            if !PAIRED_BBCODE.contains(&closing) {
+                if looks_like_tag(closing) && !unknown.contains(&raw) {
+                    unknown.push(raw);
+                }
+                continue;
+            }
+            match open.pop() {
+                Some(t) if t == closing => {}
+                Some(t) => return Err(format!("[/{closing}] closes [{t}]")),
+                None => return Err(format!("[/{closing}] is never opened")),
+            }
+            continue;
+        }
+        let name = tag.split(['=', ' ']).next().unwrap_or_default();
+        let unknown_tag = !SINGLE_BBCODE.contains(&name) && looks_like_tag(name);
+        if PAIRED_BBCODE.contains(&name) {
+            open.push(name.to_string());
+        } else if unknown_tag && !unknown.contains(&raw) {
+            unknown.push(raw);
+        }
+    }
+    match open.pop() {
+        Some(t) => Err(format!("[{t}] is never closed")),
+        None => Ok(unknown
+            .into_iter()
+            .map(|t| format!("[{t}] is not a workshop BBCode tag"))
+            .collect()),
+    }
+}
+
+/// Brackets are common in plain text, only words in them look like tags.
+fn looks_like_tag(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Whether a folder name is valid on every platform the game runs on.
+pub fn is_valid_folder_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.ends_with(['.', ' '])
+        && !name
+            .chars()
+            .any(|c| c.is_control() || "<>:\"/\\|?*".contains(c))
+}
+
+/// Whether a mod folder is named after its directory, with the workshop id appended for
+/// mods installed through Steam.
+pub fn folder_matches_directory(folder: &str, directory: &str, id: Option<&str>) -> bool {
+    folder.eq_ignore_ascii_case(directory)
+        || id.is_some_and(|id| folder.eq_ignore_ascii_case(&format!("{directory}_{}", id.trim())))
+}
+
+/// Runs every pre-upload check on a mod folder.
+pub fn validate_mod(path: &Path, ignore_patterns: &[String]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let contents = match fs::read_to_string(path.join("metadata.xml")) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.error(format!("Cannot read metadata.xml: {e}"));
+            return report;
+        }
+    };
+    let raw = match read_raw_metadata(&contents) {
+        Ok(raw) => raw,
+        Err(e) => {
+            report.error(format!("metadata.xml is not valid XML: {e}"));
+            return report;
+        }
+    };
+
+    if raw.name.as_deref().map_or(true, |n| n.trim().is_empty()) {
+        report.error("The mod has no name".to_string());
+    }
+    match raw.directory.as_deref().map(str::trim) {
+        None | Some("") => report.error("The mod has no directory".to_string()),
+        Some(directory) if !is_valid_folder_name(directory) => report.error(format!(
+            "The directory \"{directory}\" is not a valid folder name"
+        )),
+        Some(directory) => {
+            let folder = path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if !folder_matches_directory(&folder, directory, raw.id.as_deref()) {
+                report.warning(format!(
+                    "The folder \"{folder}\" does not match the directory \"{directory}\""
+                ));
+            }
+        }
+    }
+    if raw
+        .id
+        .as_deref()
+        .is_some_and(|id| id.trim().parse::<u64>().is_err())
+    {
+        report.error("The id is not a number".to_string());
+    }
+    for tag in &raw.tags {
+        if !ModTagId::ALL.iter().any(|t| t.to_string() == *tag) {
+            report.error(format!("\"{tag}\" is not a workshop tag"));
+        }
+    }
+
+    let description = raw.description.unwrap_or_default();
+    if description.trim().is_empty() {
+        report.warning("The description is empty".to_string());
+    }
+    let length = description.chars().count();
+    if length > MAX_DESCRIPTION {
+        report.error(format!(
+            "The description is {length} characters long, the workshop allows {MAX_DESCRIPTION}"
+        ));
+    }
+    match check_bbcode(&description) {
+        Ok(unknown) => {
+            for tag in unknown {
+                report.warning(format!("{tag}, it will show as plain text"));
+            }
+        }
+        Err(e) => report.error(format!("Broken BBCode in the description: {e}")),
+    }
+
+    match raw.visibility.as_deref().map(str::trim) {
+        Some(v) if VISIBILITIES.contains(&v) => {}
+        Some(v) => report.error(format!(
+            "Unknown visibility \"{v}\", use one of {}",
+            VISIBILITIES.join(", ")
+        )),
+        None => report.warning("No visibility set, the mod will be public".to_string()),
+    }
+
+    if !find_disable_markers(path).is_empty() {
+        report.error("disable.it is present and would disable the mod for everyone".to_string());
+    }
+    for file in list_files(path) {
+        let Ok(relative) = file.strip_prefix(path) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if !is_disable_marker(&relative) && is_ignored(&relative, ignore_patterns) {
+            report.warning(format!("{relative} looks like it should not be uploaded"));
+        }
+        let size = fs::metadata(&file).map_or(0, |m| m.len());
+        if size > LARGE_FILE {
+            report.warning(format!(
+                "{relative} is {} MB, make sure it is meant to be uploaded",
+                size / (1024 * 1024)
+            ));
+        }
+    }
+    report.issues.sort_by_key(|i| std::cmp::Reverse(i.severity));
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::util::test::TempDir;
+
+    use super::{check_bbcode, is_valid_folder_name, validate_mod, Severity};
+
+    #[test]
+    fn bbcode() {
+        assert!(check_bbcode("[b]Bold[/b] and [url=https://example.com]a link[/url]").is_ok());
+        assert!(check_bbcode("[list][*]One[*]Two[/list]").is_ok(), "List items do not need closing");
+        assert!(check_bbcode("Costs [3] coins [noparse][fake][/noparse]").is_ok(), "Plain brackets should not count as tags");
+        assert_eq!(check_bbcode("[b]Bold[/i]"), Err("[/i] closes [b]".to_string()));
+        assert_eq!(check_bbcode("[h1]Title"), Err("[h1] is never closed".to_string()));
+        assert_eq!(check_bbcode("[blink]x[/blink]"), Ok(vec!["[blink] is not a workshop BBCode tag".to_string(), "[/blink] is not a workshop BBCode tag".to_string()]));
+        assert!(check_bbcode("Compatible with [Repentance]").is_ok_and(|w| w.len() == 1), "Unknown words in brackets should only be warned about");
+    }
+
+    #[test]
+    fn folder_names() {
+        assert!(is_valid_folder_name("my mod"));
+        assert!(!is_valid_folder_name("my:mod"));
+        assert!(!is_valid_folder_name("mod."));
+        assert!(!is_valid_folder_name(""));
+    }
+
+    #[test]
+    fn validate_folder() {
+        let temp = TempDir::new("validate");
+        let dir = temp.join("cool mod");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("metadata.xml"), r#"<metadata><name>Cool</name><directory>cool mod</directory><description>[b]Cool[/b]</description><version>1</version><visibility>Public</visibility><tag id="Items"/></metadata>"#).unwrap();
+        let report = validate_mod(&dir, &[]);
+        assert!(report.issues.is_empty(), "A clean mod should have no issues: {:?}", report.issues);
+
+        fs::write(dir.join("disable.it"), "").unwrap();
+        fs::write(dir.join("main.lua.bak"), "").unwrap();
+        fs::write(dir.join("metadata.xml"), r#"<metadata><name>Cool</name><directory>cool mod</directory><description>Cool</description><version>1</version><visibility>Everyone</visibility><tag id="Item"/></metadata>"#).unwrap();
+        let report = validate_mod(&dir, &["*.bak".to_string()]);
+        assert!(!report.passed());
+        let errors = report.issues.iter().filter(|i| i.severity == Severity::Error).count();
+        assert_eq!(errors, 3, "Bad visibility, unknown tag and disable.it should be errors: {:?}", report.issues);
+        assert_eq!(report.issues.len(), 4, "The backup file should be a warning");
+    }
+}